@@ -0,0 +1,145 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::file::Options;
+use crate::route::routed;
+
+/// Files which belong together, such as photos and their sidecars.
+/// Companion files are sorted into the directory of the primary file with the same stem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    /// Extensions of the primary file, lowercase.
+    pub primary: Vec<String>,
+
+    /// Extensions of the companion files, lowercase.
+    pub companions: Vec<String>,
+}
+
+impl Bundle {
+    /// Creates a new bundle.
+    pub fn new(primary: &[&str], companions: &[&str]) -> Self {
+        let lowercase = |list: &[&str]| list.iter().map(|s| s.to_lowercase()).collect();
+
+        Bundle {
+            primary: lowercase(primary),
+            companions: lowercase(companions),
+        }
+    }
+
+    /// Photos with their sidecars, videos with their subtitles and shapefiles.
+    pub fn builtin() -> Vec<Bundle> {
+        vec![
+            Bundle::new(
+                &[
+                    "jpg", "jpeg", "png", "heic", "tif", "tiff", "dng", "cr2", "cr3", "nef", "arw",
+                    "raf", "orf", "rw2",
+                ],
+                &["xmp", "aae"],
+            ),
+            Bundle::new(
+                &["mp4", "mkv", "avi", "mov", "webm", "m4v"],
+                &["srt", "vtt", "ass", "ssa", "sub", "idx"],
+            ),
+            Bundle::new(&["shp"], &["shx", "dbf", "prj", "cpg", "sbn", "sbx", "qix"]),
+        ]
+    }
+
+    /// Checks whenever `extension` is the one of a primary file.
+    fn is_primary(&self, extension: &str) -> bool {
+        self.primary.contains(&extension.to_lowercase())
+    }
+
+    /// Checks whenever `extension` is the one of a companion file.
+    fn is_companion(&self, extension: &str) -> bool {
+        self.companions.contains(&extension.to_lowercase())
+    }
+}
+
+impl FromStr for Bundle {
+    type Err = String;
+
+    /// Parses a bundle, e.g. `obj,fbx:mtl` where `obj` and `fbx` are primary extensions.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = |list: &str| -> Vec<String> {
+            list.split(',')
+                .map(|s| s.trim().trim_start_matches('.').to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+
+        match s.split_once(':') {
+            Some((primary, companions)) => {
+                let bundle = Bundle {
+                    primary: split(primary),
+                    companions: split(companions),
+                };
+
+                match bundle.primary.is_empty() || bundle.companions.is_empty() {
+                    true => Err(format!("Bundle without primary or companion files: {}", s)),
+                    false => Ok(bundle),
+                }
+            }
+            None => Err(format!(
+                "Invalid bundle, expected 'primary:companions': {}",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * Resolves the extension of the primary file a companion file belongs to.
+ * The primary file is either still next to it or has already been moved into `destination`
+ * (or the directory of its route), possibly under the name given to it by `options.rename`.
+ */
+pub fn primary_extension(path: &Path, destination: &Path, options: &Options) -> Option<OsString> {
+    let extension = path.extension()?.to_str()?;
+    let stem = path.file_stem()?.to_str()?;
+    let dir = path.parent()?;
+
+    let exists = |extension: &str, name: &str| {
+        let moved = match routed(&options.routes, extension) {
+            Some(directory) => directory.to_path_buf(),
+            None => destination.join(extension),
+        };
+
+        dir.join(name).is_file()
+            || moved.join(name).is_file()
+            || moved
+                .join(options.rename.file_name(Path::new(name)))
+                .is_file()
+    };
+
+    for bundle in options.bundles.iter().filter(|b| b.is_companion(extension)) {
+        // Sidecars can keep the extension of the primary file, e.g. `IMG_1.CR2.xmp`
+        if let Some(primary) = Path::new(stem).extension().and_then(|ext| ext.to_str()) {
+            if bundle.is_primary(primary) && exists(primary, stem) {
+                return Option::Some(OsString::from(primary));
+            }
+        }
+
+        for primary in &bundle.primary {
+            for primary in [primary.clone(), primary.to_uppercase()] {
+                if exists(&primary, &format!("{}.{}", stem, primary)) {
+                    return Option::Some(OsString::from(primary));
+                }
+            }
+        }
+    }
+
+    Option::None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bundle::*;
+
+    #[test]
+    fn parse() {
+        let bundle: Bundle = "OBJ, fbx:.mtl".parse().unwrap();
+        assert_eq!(bundle, Bundle::new(&["obj", "fbx"], &["mtl"]));
+        assert!("obj".parse::<Bundle>().is_err());
+        assert!("obj:".parse::<Bundle>().is_err());
+    }
+}
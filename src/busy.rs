@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::file::Options;
+
+/// Extensions used by browsers and download managers for unfinished downloads.
+pub const DOWNLOAD_EXTENSIONS: &[&str] =
+    &["crdownload", "part", "partial", "download", "opdownload"];
+
+/// Why a file is considered to be in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusyReason {
+    /// The file is an unfinished download or there's an unfinished download for it.
+    Downloading,
+
+    /// The file has been modified recently, contains how long ago.
+    RecentlyModified(Duration),
+
+    /// The file is opened by the process with the contained id.
+    Open(u32),
+}
+
+impl fmt::Display for BusyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BusyReason::Downloading => write!(f, "unfinished download"),
+            BusyReason::RecentlyModified(age) => write!(f, "modified {}s ago", age.as_secs()),
+            BusyReason::Open(pid) => write!(f, "opened by process {}", pid),
+        }
+    }
+}
+
+/// Files currently opened by any process, mapped to the id of one of them.
+/// Only supported on linux, where it's resolved by scanning `/proc/*/fd`.
+#[derive(Debug, Default)]
+pub struct OpenFiles {
+    files: HashMap<PathBuf, u32>,
+}
+
+impl OpenFiles {
+    /// Scans the file-descriptors of all processes, the ones of other users are usually not accessible.
+    #[cfg(target_os = "linux")]
+    pub fn scan() -> Self {
+        let mut files = HashMap::new();
+        let processes = match std::fs::read_dir("/proc") {
+            Ok(processes) => processes,
+            Err(_) => return OpenFiles { files },
+        };
+
+        for process in processes.flatten() {
+            let pid = match process.file_name().to_str().and_then(|s| s.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+
+            let descriptors = match std::fs::read_dir(process.path().join("fd")) {
+                Ok(descriptors) => descriptors,
+                Err(_) => continue,
+            };
+
+            for descriptor in descriptors.flatten() {
+                if let Ok(file) = std::fs::read_link(descriptor.path()) {
+                    files.entry(file).or_insert(pid);
+                }
+            }
+        }
+
+        OpenFiles { files }
+    }
+
+    /// Open files can't be resolved on this platform.
+    #[cfg(not(target_os = "linux"))]
+    pub fn scan() -> Self {
+        OpenFiles::default()
+    }
+
+    /// Returns the id of a process which opened the file, if any.
+    pub fn opened_by(&self, path: &Path) -> Option<u32> {
+        if self.files.is_empty() {
+            return Option::None;
+        }
+
+        // Links of file-descriptors are always canonical
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.files.get(&path).copied()
+    }
+}
+
+/// Checks whenever a file is an unfinished download or has one next to it, e.g. `a.pdf.part`.
+fn is_downloading(path: &Path) -> bool {
+    let is_download_extension = |path: &Path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| DOWNLOAD_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    };
+
+    if is_download_extension(path) {
+        return true;
+    }
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+
+    DOWNLOAD_EXTENSIONS.iter().any(|ext| {
+        let mut download = name.clone();
+        download.push(ext);
+        path.with_file_name(download).exists()
+    })
+}
+
+/**
+ * Checks whenever a file is still in use and shouldn't be moved yet.
+ */
+pub fn check(path: &Path, options: &Options, open_files: &OpenFiles) -> Option<BusyReason> {
+    if options.busy_check && is_downloading(path) {
+        return Option::Some(BusyReason::Downloading);
+    }
+
+    if let Some(min_age) = options.min_age {
+        let age = std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        if let Some(age) = age.filter(|age| *age < min_age) {
+            return Option::Some(BusyReason::RecentlyModified(age));
+        }
+    }
+
+    if options.busy_check {
+        if let Some(pid) = open_files.opened_by(path) {
+            return Option::Some(BusyReason::Open(pid));
+        }
+    }
+
+    Option::None
+}
@@ -0,0 +1,424 @@
+use std::str::FromStr;
+
+use crate::cli::flag::Multiplicity;
+use crate::cli::CLIApp;
+
+/// What a flag or value gets completed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Completion {
+    /// Nothing is suggested.
+    Nothing,
+
+    /// Files and directories.
+    Files,
+
+    /// Directories only.
+    Directories,
+
+    /// A fixed list of words.
+    Words(Vec<String>),
+}
+
+/// A shell completion-scripts can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!("Unknown shell: {} (expected bash, zsh or fish)", s)),
+        }
+    }
+}
+
+/// Escapes a string so it can be used inside of single quotes.
+fn quote(s: &str, escaped_quote: &str) -> String {
+    s.replace('\'', escaped_quote)
+}
+
+impl CLIApp {
+    /// Generates a completion-script for the given shell.
+    pub fn completion_script(&self, shell: Shell) -> String {
+        match shell {
+            Shell::Bash => self.bash_completion(),
+            Shell::Zsh => self.zsh_completion(),
+            Shell::Fish => self.fish_completion(),
+        }
+    }
+
+    /// Completion of values, the one of the first value is used for all of them.
+    fn value_completion(&self) -> &Completion {
+        self.values
+            .first()
+            .map_or(&Completion::Nothing, |val| &val.completion)
+    }
+
+    fn bash_completion(&self) -> String {
+        let compgen = |completion: &Completion| match completion {
+            Completion::Nothing => String::from("return"),
+            Completion::Files => String::from("COMPREPLY+=($(compgen -f -- \"$cur\")); return"),
+            Completion::Directories => {
+                String::from("COMPREPLY+=($(compgen -d -- \"$cur\")); return")
+            }
+            Completion::Words(words) => format!(
+                "COMPREPLY+=($(compgen -W \"{}\" -- \"$cur\")); return",
+                words.join(" ")
+            ),
+        };
+
+        let function = format!("_{}", self.name.replace('-', "_"));
+        let mut script = format!(
+            "{}() {{\n    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    COMPREPLY=()\n\n",
+            function
+        );
+
+        // Values of subcommands
+        if !self.subcommands.is_empty() {
+            script.push_str(
+                "    if [[ $COMP_CWORD -gt 1 ]]; then\n        case \"${COMP_WORDS[1]}\" in\n",
+            );
+
+            for subcommand in &self.subcommands {
+                script.push_str(&format!(
+                    "            {})\n                {}\n                ;;\n",
+                    subcommand.name,
+                    compgen(subcommand.value_completion())
+                ));
+            }
+
+            script.push_str("        esac\n    fi\n\n");
+        }
+
+        script.push_str("    case \"$prev\" in\n");
+
+        for flag in self.flags.iter().filter(|flag| flag.expects_value) {
+            script.push_str(&format!(
+                "        {})\n            {}\n            ;;\n",
+                flag.abbr.join("|"),
+                compgen(&flag.completion)
+            ));
+        }
+
+        let abbreviations: Vec<String> = self
+            .flags
+            .iter()
+            .flat_map(|flag| flag.abbreviations())
+            .collect();
+
+        script.push_str(&format!(
+            "    esac\n\n    if [[ \"$cur\" == -* ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n        return\n    fi\n\n",
+            abbreviations.join(" ")
+        ));
+
+        // Subcommands are suggested along with the first value
+        if !self.subcommands.is_empty() {
+            let names: Vec<&str> = self.subcommands.iter().map(|s| s.name.as_str()).collect();
+            script.push_str(&format!(
+                "    if [[ $COMP_CWORD -eq 1 ]]; then\n        COMPREPLY+=($(compgen -W \"{}\" -- \"$cur\"))\n    fi\n\n",
+                names.join(" ")
+            ));
+        }
+
+        script.push_str(&format!(
+            "    {}\n}}\n\ncomplete -F {} {}\n",
+            compgen(self.value_completion()),
+            function,
+            self.name
+        ));
+
+        script
+    }
+
+    fn zsh_completion(&self) -> String {
+        let action = |completion: &Completion| match completion {
+            Completion::Nothing => String::from(" "),
+            Completion::Files => String::from("_files"),
+            Completion::Directories => String::from("_files -/"),
+            Completion::Words(words) => format!("({})", words.join(" ")),
+        };
+
+        let escape = |s: &str| {
+            quote(s, "'\\''")
+                .replace('[', "\\[")
+                .replace(']', "\\]")
+                .replace(':', "\\:")
+        };
+
+        // Same as action, but used as command in a case-branch
+        let command = |completion: &Completion| match completion {
+            Completion::Nothing => String::from(":"),
+            Completion::Files => String::from("_files"),
+            Completion::Directories => String::from("_files -/"),
+            Completion::Words(words) => format!("compadd -- {}", words.join(" ")),
+        };
+
+        let mut script = format!("#compdef {}\n\n", self.name);
+        if !self.subcommands.is_empty() {
+            script.push_str("local context state state_descr line\ntypeset -A opt_args\n\n");
+        }
+
+        script.push_str("_arguments -s \\\n");
+
+        for flag in &self.flags {
+            let abbr = flag.abbreviations();
+
+            // Flags which may be passed multiple times are offered again instead of excluding
+            // themselves
+            let exclusion = match flag.multiplicity {
+                Multiplicity::Once => format!("'({})'", abbr.join(" ")),
+                _ => String::from("'*'"),
+            };
+
+            let mut spec = format!(
+                "{}{{{}}}'[{}]",
+                exclusion,
+                abbr.join(","),
+                escape(&flag.description)
+            );
+
+            // Brace expansion requires at least two abbreviations
+            if abbr.len() == 1 {
+                let prefix = match flag.multiplicity {
+                    Multiplicity::Once => "",
+                    _ => "*",
+                };
+
+                spec = format!("'{}{}[{}]", prefix, abbr[0], escape(&flag.description));
+            }
+
+            // An empty message would mark the value as optional
+            if flag.expects_value {
+                let message = match flag.value_description.is_empty() {
+                    true => &flag.name,
+                    false => &flag.value_description,
+                };
+
+                spec.push_str(&format!(
+                    ":{}:{}",
+                    escape(message),
+                    action(&flag.completion)
+                ));
+            }
+
+            script.push_str(&format!("  {}' \\\n", spec));
+        }
+
+        // Subcommands are suggested along with the first value, like for bash
+        if !self.subcommands.is_empty() {
+            let commands: Vec<String> = self
+                .subcommands
+                .iter()
+                .map(|s| format!("{}\\:{}", s.name, escape(&s.about).replace(' ', "\\ ")))
+                .collect();
+
+            let values: Vec<String> = self
+                .subcommands
+                .iter()
+                .map(|s| format!("      {}) {} ;;\n", s.name, command(s.value_completion())))
+                .collect();
+
+            let name = self.values.first().map_or("value", |val| val.name.as_str());
+            script.push_str(&format!(
+                "  '1: :->first' \\\n  '*: :->rest'\n\ncase $state in\n  first)\n    _alternative 'commands:command:(({}))' 'values:{}:{}'\n    ;;\n  rest)\n    case $line[1] in\n{}      *) {} ;;\n    esac\n    ;;\nesac\n",
+                commands.join(" "),
+                escape(name),
+                action(self.value_completion()),
+                values.concat(),
+                command(self.value_completion())
+            ));
+
+            return script;
+        }
+
+        for val in &self.values {
+            let prefix = if val.variadic { "*" } else { "" };
+            script.push_str(&format!(
+                "  '{}:{}:{}' \\\n",
+                prefix,
+                escape(&val.name),
+                action(&val.completion)
+            ));
+
+            // Everything following a variadic value is consumed by it
+            if val.variadic {
+                break;
+            }
+        }
+
+        script.truncate(script.len() - 3);
+        script.push('\n');
+        script
+    }
+
+    fn fish_completion(&self) -> String {
+        let mut script = format!("complete -c {} -f\n", self.name);
+
+        for flag in &self.flags {
+            let mut line = format!("complete -c {}", self.name);
+
+            for abbr in &flag.abbreviations() {
+                match abbr.strip_prefix("--") {
+                    Some(long) => line.push_str(&format!(" -l {}", long)),
+                    None => line.push_str(&format!(" -s {}", abbr.trim_start_matches('-'))),
+                }
+            }
+
+            if flag.expects_value {
+                line.push_str(match &flag.completion {
+                    Completion::Files => " -r -F",
+                    Completion::Directories => " -x -a '(__fish_complete_directories)'",
+                    _ => " -x",
+                });
+
+                if let Completion::Words(words) = &flag.completion {
+                    line.push_str(&format!(" -a '{}'", quote(&words.join(" "), "\\'")));
+                }
+            }
+
+            line.push_str(&format!(" -d '{}'\n", quote(&flag.description, "\\'")));
+            script.push_str(&line);
+        }
+
+        for subcommand in &self.subcommands {
+            script.push_str(&format!(
+                "complete -c {} -n __fish_use_subcommand -a {} -d '{}'\n",
+                self.name,
+                subcommand.name,
+                quote(&subcommand.about, "\\'")
+            ));
+
+            if let Completion::Words(words) = subcommand.value_completion() {
+                script.push_str(&format!(
+                    "complete -c {} -n '__fish_seen_subcommand_from {}' -a '{}'\n",
+                    self.name,
+                    subcommand.name,
+                    quote(&words.join(" "), "\\'")
+                ));
+            }
+        }
+
+        match self.value_completion() {
+            Completion::Nothing => (),
+            Completion::Files => script.push_str(&format!("complete -c {} -F\n", self.name)),
+            Completion::Directories => script.push_str(&format!(
+                "complete -c {} -a '(__fish_complete_directories)'\n",
+                self.name
+            )),
+            Completion::Words(words) => script.push_str(&format!(
+                "complete -c {} -a '{}'\n",
+                self.name,
+                quote(&words.join(" "), "\\'")
+            )),
+        }
+
+        script
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::completion::*;
+    use crate::cli::flag::{CLIFlag, Multiplicity};
+    use crate::cli::value::CLIValue;
+
+    fn app() -> CLIApp {
+        CLIApp::new()
+            .name("app")
+            .add_flag(
+                CLIFlag::new("dry")
+                    .description("Don't [do] it")
+                    .env("APP_DRY")
+                    .abbr("-d")
+                    .abbr("--dry"),
+            )
+            .add_flag(
+                CLIFlag::new("log")
+                    .description("Log-file")
+                    .expects_value(true)
+                    .value_description("file")
+                    .complete(Completion::Files)
+                    .abbr("--log"),
+            )
+            .add_flag(
+                CLIFlag::new("ext")
+                    .expects_value(true)
+                    .complete(Completion::Words(vec![
+                        String::from("txt"),
+                        String::from("pdf"),
+                    ]))
+                    .multiplicity(Multiplicity::Repeated)
+                    .abbr("-e"),
+            )
+            .add_value(
+                CLIValue::new("source")
+                    .variadic(true)
+                    .complete(Completion::Directories),
+            )
+            .add_subcommand(
+                CLIApp::new()
+                    .name("shell")
+                    .about("Prints a script")
+                    .add_value(CLIValue::new("kind").complete(Completion::Words(vec![
+                        String::from("bash"),
+                        String::from("fish"),
+                    ]))),
+            )
+    }
+
+    #[test]
+    fn bash() {
+        let script = app().completion_script(Shell::Bash);
+        assert!(script.contains(
+            "        --log)\n            COMPREPLY+=($(compgen -f -- \"$cur\")); return\n"
+        ));
+        assert!(script.contains(
+            "        -e)\n            COMPREPLY+=($(compgen -W \"txt pdf\" -- \"$cur\")); return\n"
+        ));
+        assert!(script.contains("compgen -W \"-d --dry --no-dry --log -e\""));
+        assert!(script.contains(
+            "            shell)\n                COMPREPLY+=($(compgen -W \"bash fish\" -- \"$cur\")); return\n"
+        ));
+        assert!(script.contains(
+            "    if [[ $COMP_CWORD -eq 1 ]]; then\n        COMPREPLY+=($(compgen -W \"shell\" -- \"$cur\"))\n"
+        ));
+        assert!(script.ends_with("complete -F _app app\n"));
+    }
+
+    #[test]
+    fn zsh() {
+        let script = app().completion_script(Shell::Zsh);
+        assert!(script.starts_with("#compdef app\n"));
+        assert!(script
+            .contains("  '(-d --dry --no-dry)'{-d,--dry,--no-dry}'[Don'\\''t \\[do\\] it]' \\\n"));
+        assert!(script.contains("  '--log[Log-file]:file:_files' \\\n"));
+        assert!(script.contains("  '*-e[]:ext:(txt pdf)' \\\n"));
+        assert!(script.contains("  '1: :->first' \\\n  '*: :->rest'\n"));
+        assert!(script.contains(
+            "    _alternative 'commands:command:((shell\\:Prints\\ a\\ script))' 'values:source:_files -/'\n"
+        ));
+        assert!(script.contains("      shell) compadd -- bash fish ;;\n      *) _files -/ ;;\n"));
+    }
+
+    #[test]
+    fn fish() {
+        let script = app().completion_script(Shell::Fish);
+        assert!(script.contains("complete -c app -s d -l dry -l no-dry -d 'Don\\'t [do] it'\n"));
+        assert!(script.contains("complete -c app -l log -r -F -d 'Log-file'\n"));
+        assert!(script.contains("complete -c app -s e -x -a 'txt pdf' -d ''\n"));
+        assert!(script
+            .contains("complete -c app -n __fish_use_subcommand -a shell -d 'Prints a script'\n"));
+        assert!(script
+            .contains("complete -c app -n '__fish_seen_subcommand_from shell' -a 'bash fish'\n"));
+        assert!(script.ends_with("complete -c app -a '(__fish_complete_directories)'\n"));
+        assert!("powershell".parse::<Shell>().is_err());
+    }
+}
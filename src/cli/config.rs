@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::cli::constraints::conflicting;
+use crate::cli::flag::{CLIFlag, Multiplicity};
+use crate::cli::result::CLIResult;
+use crate::cli::suggest::did_you_mean;
+use crate::cli::types::{Boolean, ValueType};
+use crate::cli::CLIApp;
+
+/// Where the value of a flag comes from, flags without one fall back to their default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Passed as cli-argument.
+    CommandLine,
+
+    /// Read from the contained environment variable.
+    Env(String),
+
+    /// Read from the config-file at the contained line.
+    Config(PathBuf, usize),
+
+    /// Turned on by default.
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::CommandLine => write!(f, "command line"),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::Config(path, line) => write!(f, "{}:{}", path.display(), line),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/**
+ * Parses a config-file consisting of `key = value` lines, empty lines and lines starting with `#`
+ * are skipped. Returns the line-number, key and value of each entry.
+ */
+pub fn parse_config(content: &str) -> Result<Vec<(usize, String, String)>, String> {
+    let mut entries = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) => {
+                entries.push((index + 1, key.trim().to_string(), value.trim().to_string()))
+            }
+            None => return Err(format!("Line {}: expected key = value", index + 1)),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Key of a flag in the config-file, e.g. `exclude` for `--exclude`.
+fn config_key(flag: &CLIFlag) -> &str {
+    flag.long_abbr().trim_start_matches('-')
+}
+
+impl CLIApp {
+    /// Sets the config-file flags with an environment variable fall back to, it doesn't have to exist.
+    pub fn config_file(mut self, path: &Path) -> Self {
+        self.config_file = Option::Some(path.to_path_buf());
+        self
+    }
+
+    /// Sets how the config-file is referred to in the man-page, e.g. `$XDG_CONFIG_HOME/app/config`
+    /// instead of the path resolved for the user who happens to render it.
+    pub fn config_location(mut self, location: &str) -> Self {
+        self.config_location = Option::Some(location.to_string());
+        self
+    }
+
+    /// Reads the entries of the config-file, if there is one.
+    fn read_config(&self) -> Result<Vec<(usize, String, String)>, String> {
+        let path = match &self.config_file {
+            Some(path) => path,
+            None => return Ok(Vec::new()),
+        };
+
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read config-file {:?} ({})", path, e)),
+        };
+
+        let entries = parse_config(&content).map_err(|e| format!("{} of {:?}", e, path))?;
+        let configurable = || self.flags.iter().filter(|flag| flag.env.is_some());
+
+        // Catch typos even if they're overridden
+        for (line, key, _) in &entries {
+            if !configurable().any(|flag| config_key(flag) == key) {
+                return Err(did_you_mean(
+                    format!("Unknown option {} in {}:{}", key, path.display(), line),
+                    key,
+                    configurable().map(config_key),
+                ));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Sets flags which haven't been passed as cli-argument out of their environment variable,
+    /// or the config-file.
+    pub fn apply_fallbacks(&self, result: &mut CLIResult) -> Result<(), String> {
+        let config = self.read_config()?;
+
+        // The environment takes precedence over the config-file, so it's applied first
+        for from_env in [true, false] {
+            for flag in &self.flags {
+                self.apply_fallback(flag, from_env, &config, result)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets a single flag out of its environment variable or the config-file, unless it's already
+    /// set or a flag conflicting with it has been set with a higher precedence.
+    fn apply_fallback(
+        &self,
+        flag: &CLIFlag,
+        from_env: bool,
+        config: &[(usize, String, String)],
+        result: &mut CLIResult,
+    ) -> Result<(), String> {
+        let env = match &flag.env {
+            Some(env) if result.source(&flag.name).is_none() => env,
+            _ => return Ok(()),
+        };
+
+        let entries: Vec<(String, Source)> = match std::env::var(env) {
+            Ok(value) if !value.is_empty() => match from_env {
+                true => vec![(value, Source::Env(env.clone()))],
+                false => return Ok(()),
+            },
+            _ if from_env => return Ok(()),
+            _ => config
+                .iter()
+                .filter(|(_, key, _)| key == config_key(flag))
+                .map(|(line, _, value)| {
+                    let path = self.config_file.clone().unwrap_or_default();
+                    (value.clone(), Source::Config(path, *line))
+                })
+                .collect(),
+        };
+
+        // E.g. --include on the command line drops exclude of the config-file, conflicts between
+        // flags of the same source are reported later on
+        let overridden = self.flags.iter().any(|other| {
+            conflicting(flag, other)
+                && match result.source(&other.name) {
+                    Some(Source::CommandLine) => true,
+                    Some(Source::Env(_)) => !from_env,
+                    _ => false,
+                }
+        });
+
+        if overridden {
+            return Ok(());
+        }
+
+        for (value, source) in entries {
+            let invalid =
+                |e: String| format!("Invalid value for {} ({}): {}", flag.long_abbr(), source, e);
+
+            // Flags without value are turned on or off, counted ones take the count
+            let values = match (flag.expects_value, flag.multiplicity) {
+                (true, _) => {
+                    flag.value_type.check(&value).map_err(invalid)?;
+                    if let Some(validator) = flag.validator {
+                        validator(&value).map_err(invalid)?;
+                    }
+
+                    // Relative paths of the config-file are relative to its directory
+                    match (&flag.value_type, &source) {
+                        (ValueType::Path, Source::Config(path, _))
+                            if Path::new(&value).is_relative() =>
+                        {
+                            let dir = path.parent().unwrap_or_else(|| Path::new(""));
+                            vec![dir.join(&value).to_string_lossy().to_string()]
+                        }
+                        _ => vec![value],
+                    }
+                }
+                (false, Multiplicity::Counted) => match value.parse::<usize>() {
+                    Ok(count) => vec![String::new(); count],
+                    Err(_) => return Err(invalid(format!("expected a count but got {}", value))),
+                },
+                (false, _) => match value.parse::<Boolean>() {
+                    Ok(Boolean(true)) => vec![String::new()],
+                    Ok(Boolean(false)) => Vec::new(),
+                    Err(e) => return Err(invalid(e)),
+                },
+            };
+
+            // Only the last entry is kept of flags which can only be passed once
+            if flag.multiplicity == Multiplicity::Once {
+                result.remove(&flag.name);
+            }
+
+            result.insert(&flag.name, flag.expects_value, values, source);
+        }
+
+        Ok(())
+    }
+
+    /// Lists the effective value of each flag which can be configured along with where it comes
+    /// from, in the format of the config-file.
+    pub fn config_report(&self, result: &CLIResult) -> String {
+        let mut report = match &self.config_file {
+            Some(path) if path.exists() => format!("# Config-file: {}\n", path.display()),
+            Some(path) => format!("# Config-file: {} (not found)\n", path.display()),
+            None => String::new(),
+        };
+
+        let mut rows: Vec<(String, String)> = Vec::new();
+        for flag in self.flags.iter().filter(|flag| flag.env.is_some()) {
+            let key = config_key(flag);
+            let source = result
+                .source(&flag.name)
+                .map_or_else(|| String::from("default"), |source| source.to_string());
+
+            if !flag.expects_value {
+                let value = match flag.multiplicity {
+                    Multiplicity::Counted => result.count(&flag.name).to_string(),
+                    _ => result.has_flag(&flag.name).to_string(),
+                };
+
+                rows.push((format!("{} = {}", key, value), source));
+                continue;
+            }
+
+            let values = result.get_args(&flag.name);
+            if !values.is_empty() {
+                for value in values {
+                    rows.push((format!("{} = {}", key, value), source.clone()));
+                }
+            } else if let Some(default) = flag.resolve_default(&HashMap::new()) {
+                rows.push((format!("{} = {}", key, default), source));
+            } else {
+                rows.push((format!("# {} is not set", key), source));
+            }
+        }
+
+        let width = rows.iter().map(|(row, _)| row.len()).max().unwrap_or(0);
+        for (row, source) in rows {
+            report.push_str(&format!("{: <width$}  # {}\n", row, source, width = width));
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::config::*;
+
+    #[test]
+    fn parse() {
+        let entries =
+            parse_config("# Comment\n\nexclude = tmp,part\n  log-file=a = b  \n").unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (3, String::from("exclude"), String::from("tmp,part")),
+                (4, String::from("log-file"), String::from("a = b")),
+            ]
+        );
+
+        assert_eq!(
+            parse_config("dry-run\n"),
+            Err(String::from("Line 1: expected key = value"))
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let path = std::env::temp_dir().join(format!("cleanup-config-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "exclude = a\nexclude = b\ndry-run = yes\nlog-file = config.log\n",
+        )
+        .unwrap();
+
+        std::env::set_var("CLEANUP_TEST_LOG", "env.log");
+        let app = CLIApp::new()
+            .config_file(&path)
+            .add_flag(
+                CLIFlag::new("dry")
+                    .env("CLEANUP_TEST_DRY")
+                    .abbr("--dry-run"),
+            )
+            .add_flag(
+                CLIFlag::new("log")
+                    .expects_value(true)
+                    .env("CLEANUP_TEST_LOG")
+                    .abbr("--log-file"),
+            )
+            .add_flag(
+                CLIFlag::new("exclude")
+                    .expects_value(true)
+                    .multiplicity(Multiplicity::Repeated)
+                    .env("CLEANUP_TEST_EXCLUDE")
+                    .abbr("--exclude"),
+            )
+            .add_flag(
+                CLIFlag::new("min")
+                    .value_type(ValueType::Integer)
+                    .env("CLEANUP_TEST_MIN")
+                    .abbr("--min"),
+            );
+
+        let result = app
+            .consume(vec!["", "--min", "3"].into_iter().map(String::from))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.get_arg("min").unwrap(), "3");
+        assert_eq!(result.source("min"), Some(&Source::CommandLine));
+        assert_eq!(result.get_arg("log").unwrap(), "env.log");
+        assert_eq!(
+            result.source("log"),
+            Some(&Source::Env(String::from("CLEANUP_TEST_LOG")))
+        );
+        assert_eq!(result.get_args("exclude"), ["a", "b"]);
+        assert!(result.has_flag("dry"));
+        assert_eq!(result.source("dry"), Some(&Source::Config(path.clone(), 3)));
+
+        let report = app.config_report(&result);
+        assert!(report.contains("log-file = env.log  # env CLEANUP_TEST_LOG\n"));
+        let source = format!("# {}:2", path.display());
+        assert!(report
+            .lines()
+            .any(|line| line.starts_with("exclude = b ") && line.ends_with(&source)));
+    }
+
+    #[test]
+    fn deferred() {
+        let dir = std::env::temp_dir().join(format!("cleanup-config-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        let app = CLIApp::new()
+            .config_file(&path)
+            .add_flag(
+                CLIFlag::new("target")
+                    .value_type(ValueType::Path)
+                    .env("CLEANUP_TEST_TARGET")
+                    .abbr("--target"),
+            )
+            .add_flag(CLIFlag::new("help").abbr("--help"));
+
+        std::fs::write(
+            &path,
+            "target = inbox
+",
+        )
+        .unwrap();
+        let result = app.consume(vec![String::from("")].into_iter()).unwrap();
+        assert_eq!(
+            result.get_arg("target"),
+            Some(&dir.join("inbox").to_string_lossy().to_string())
+        );
+        assert!(result.deferred_error().is_none());
+
+        std::fs::write(
+            &path,
+            "unknown = yes
+",
+        )
+        .unwrap();
+        let result = app
+            .consume(vec!["", "--help"].into_iter().map(String::from))
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.has_flag("help"));
+        assert!(result.deferred_error().unwrap().contains("unknown"));
+    }
+}
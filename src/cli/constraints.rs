@@ -0,0 +1,216 @@
+use crate::cli::config::Source;
+use crate::cli::flag::CLIFlag;
+use crate::cli::result::CLIResult;
+use crate::cli::CLIApp;
+
+/// Checks whenever a flag has been set, no matter if by cli-argument, environment variable or
+/// config-file.
+fn is_set(result: &CLIResult, flag: &CLIFlag) -> bool {
+    result.has_flag(&flag.name) || result.has_arg(&flag.name)
+}
+
+/// Refers to a flag in errors, along with where it's been set if that's not the command line.
+fn describe(result: &CLIResult, flag: &CLIFlag) -> String {
+    match result.source(&flag.name) {
+        Some(Source::CommandLine) | None => flag.long_abbr().to_string(),
+        Some(source) => format!("{} ({})", flag.long_abbr(), source),
+    }
+}
+
+/**
+ * Checks whenever two flags can't be passed together, either because one of them conflicts with
+ * the other or because both are part of the same exclusive group.
+ */
+pub fn conflicting(a: &CLIFlag, b: &CLIFlag) -> bool {
+    a.name != b.name
+        && (a.conflicts.contains(&b.name)
+            || b.conflicts.contains(&a.name)
+            || (a.group.is_some() && a.group == b.group))
+}
+
+impl CLIApp {
+    /// Checks conflicting, required and exclusive flags of the (sub-)command which matched.
+    /// Panics if a constraint refers to an unknown flag.
+    pub fn check_constraints(&self, result: &CLIResult) -> Result<(), String> {
+        let (app, globals) = self.matched(result);
+        let result = result.innermost();
+        let flags: Vec<&CLIFlag> = app.flags.iter().chain(globals).collect();
+        let set: Vec<&CLIFlag> = flags
+            .iter()
+            .copied()
+            .filter(|flag| is_set(result, flag))
+            .collect();
+
+        for (index, flag) in set.iter().enumerate() {
+            for other in &set[index + 1..] {
+                if !conflicting(flag, other) {
+                    continue;
+                }
+
+                // Exclusive groups list all of their flags to make clear which one to drop
+                return Err(match &flag.group {
+                    Some(group) if flag.group == other.group => {
+                        let members: Vec<&str> = flags
+                            .iter()
+                            .filter(|member| member.group.as_ref() == Some(group))
+                            .map(|member| member.long_abbr())
+                            .collect();
+
+                        format!(
+                            "Only one of {} may be passed but got {} and {}.",
+                            members.join(", "),
+                            describe(result, flag),
+                            describe(result, other)
+                        )
+                    }
+                    _ => format!(
+                        "Flag {} can't be combined with {}.",
+                        describe(result, flag),
+                        describe(result, other)
+                    ),
+                });
+            }
+        }
+
+        for flag in &set {
+            for name in &flag.requires {
+                let required = match flags.iter().find(|other| other.name.eq(name)) {
+                    Some(required) => required,
+                    None => panic!("Flag \"{}\" requires unknown flag \"{}\".", flag.name, name),
+                };
+
+                if !is_set(result, required) {
+                    return Err(format!(
+                        "Flag {} requires {}.",
+                        describe(result, flag),
+                        required.long_abbr()
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describes which flags can't be combined with or are required by `flag`, appended to its
+    /// description in the help-text and man-page, e.g. ` Requires --dry-run.`.
+    pub fn constraints_of(&self, flag: &CLIFlag, globals: &[&CLIFlag]) -> String {
+        let flags: Vec<&CLIFlag> = self.flags.iter().chain(globals.iter().copied()).collect();
+        let mut description = String::new();
+
+        let conflicts: Vec<&str> = flags
+            .iter()
+            .filter(|other| conflicting(flag, other))
+            .map(|other| other.long_abbr())
+            .collect();
+
+        if !conflicts.is_empty() {
+            description.push_str(&format!(
+                " Can't be combined with {}.",
+                conflicts.join(", ")
+            ));
+        }
+
+        let requires: Vec<&str> = flags
+            .iter()
+            .filter(|other| flag.requires.contains(&other.name))
+            .map(|other| other.long_abbr())
+            .collect();
+
+        if !requires.is_empty() {
+            description.push_str(&format!(" Requires {}.", requires.join(", ")));
+        }
+
+        description
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::constraints::*;
+
+    fn app() -> CLIApp {
+        CLIApp::new()
+            .add_flag(CLIFlag::new("dry").abbr("-d").abbr("--dry-run"))
+            .add_flag(
+                CLIFlag::new("plan")
+                    .expects_value(true)
+                    .requires("dry")
+                    .abbr("--plan-out"),
+            )
+            .add_flag(
+                CLIFlag::new("include")
+                    .expects_value(true)
+                    .conflicts_with("exclude")
+                    .env("CONSTRAINTS_TEST_INCLUDE")
+                    .abbr("--include"),
+            )
+            .add_flag(
+                CLIFlag::new("exclude")
+                    .expects_value(true)
+                    .env("CONSTRAINTS_TEST_EXCLUDE")
+                    .abbr("--exclude"),
+            )
+            .add_flag(
+                CLIFlag::new("restore")
+                    .exclusive_group("mode")
+                    .abbr("--restore"),
+            )
+            .add_flag(
+                CLIFlag::new("expire")
+                    .exclusive_group("mode")
+                    .abbr("--expire"),
+            )
+            .add_flag(
+                CLIFlag::new("shrink")
+                    .exclusive_group("mode")
+                    .abbr("--shrink"),
+            )
+    }
+
+    #[test]
+    fn check() {
+        let app = app();
+        let consume = |args: Vec<&str>| app.consume(args.into_iter().map(String::from));
+
+        assert!(consume(vec!["", "--plan-out", "a", "-d", "--include", "a"]).is_ok());
+        assert!(consume(vec!["", "--restore", "--exclude", "a"]).is_ok());
+
+        assert_eq!(
+            consume(vec!["", "--plan-out", "a"]).err().unwrap(),
+            "Flag --plan-out requires --dry-run."
+        );
+        assert_eq!(
+            consume(vec!["", "--exclude", "a", "--include", "b"])
+                .err()
+                .unwrap(),
+            "Flag --include can't be combined with --exclude."
+        );
+        assert_eq!(
+            consume(vec!["", "--shrink", "--restore"]).err().unwrap(),
+            "Only one of --restore, --expire, --shrink may be passed but got --restore and --shrink."
+        );
+
+        // Flags passed as cli-argument drop conflicting ones of the environment
+        std::env::set_var("CONSTRAINTS_TEST_EXCLUDE", "a");
+        let result = consume(vec!["", "--include", "b"]).unwrap();
+        assert_eq!(result.get_arg("include").unwrap(), "b");
+        assert!(!result.has_arg("exclude"));
+
+        std::env::set_var("CONSTRAINTS_TEST_INCLUDE", "b");
+        assert_eq!(
+            consume(vec![""]).err().unwrap(),
+            "Flag --include (env CONSTRAINTS_TEST_INCLUDE) can't be combined with --exclude (env CONSTRAINTS_TEST_EXCLUDE)."
+        );
+        std::env::remove_var("CONSTRAINTS_TEST_INCLUDE");
+        std::env::remove_var("CONSTRAINTS_TEST_EXCLUDE");
+    }
+
+    #[test]
+    fn help() {
+        let help = app().help_text();
+        assert!(help.contains(" Requires --dry-run.\n"));
+        assert!(help.contains(" Can't be combined with --include.\n"));
+        assert!(help.contains(" Can't be combined with --expire, --shrink.\n"));
+    }
+}
@@ -1,14 +1,41 @@
 use std::collections::HashMap;
 
+use crate::cli::completion::Completion;
+use crate::cli::types::ValueType;
+use crate::cli::{DefaultFn, ValidatorFn};
+
+/// How often a flag may be passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplicity {
+    /// At most once.
+    Once,
+
+    /// Any number of times, all values are collected.
+    Repeated,
+
+    /// Any number of times, e.g. `-vvv`, only the number of occurrences matters.
+    Counted,
+}
+
 /// A CLIFlag represents a flag passed as cli-argument.
 pub struct CLIFlag {
     pub name: String,
-    pub default: Option<fn(&HashMap<String, String>) -> String>,
+    pub default: Option<DefaultFn>,
     pub description: String,
     pub value_description: String,
     pub expects_value: bool,
-    pub validator: Option<fn(&String) -> Result<(), String>>,
+    pub validator: Option<ValidatorFn>,
     pub abbr: Vec<String>,
+    pub completion: Completion,
+    pub global: bool,
+    pub informational: bool,
+    pub multiplicity: Multiplicity,
+    pub value_type: ValueType,
+    pub env: Option<String>,
+    pub conflicts: Vec<String>,
+    pub requires: Vec<String>,
+    pub group: Option<String>,
+    pub enabled: bool,
 }
 
 /// Represents a cli-flag.
@@ -25,6 +52,16 @@ impl CLIFlag {
             expects_value: false,
             abbr: Vec::new(),
             validator: Option::None,
+            completion: Completion::Nothing,
+            global: false,
+            informational: false,
+            multiplicity: Multiplicity::Once,
+            value_type: ValueType::Text,
+            env: Option::None,
+            conflicts: Vec::new(),
+            requires: Vec::new(),
+            group: Option::None,
+            enabled: false,
         }
     }
 
@@ -41,14 +78,14 @@ impl CLIFlag {
     }
 
     /// Sets a default value
-    pub fn default(mut self, default: fn(&HashMap<String, String>) -> String) -> Self {
+    pub fn default(mut self, default: DefaultFn) -> Self {
         self.default = Option::Some(default);
         self.expects_value = true;
         self
     }
 
     pub fn resolve_default(&self, map: &HashMap<String, String>) -> Option<String> {
-        self.default.and_then(|func| Option::Some(func(map)))
+        self.default.map(|func| func(map))
     }
 
     /// Updates the description of the value
@@ -65,31 +102,165 @@ impl CLIFlag {
         self
     }
 
+    /// Turns a flag without value on unless it's negated, e.g. by `--no-log`
+    pub fn enabled_by_default(mut self, enabled: bool) -> Self {
+        if self.expects_value {
+            panic!(
+                "Tried to enable flag '{}' by default which expects a value.",
+                self.name
+            )
+        }
+
+        self.enabled = enabled;
+        self
+    }
+
     /// Sets a validator for this flag
-    pub fn validate(mut self, validator: fn(&String) -> Result<(), String>) -> Self {
+    pub fn validate(mut self, validator: ValidatorFn) -> Self {
         self.validator = Option::Some(validator);
         self
     }
 
+    /// Sets what shell-completions suggest for this one
+    pub fn complete(mut self, completion: Completion) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    /// Makes this flag available in all subcommands
+    pub fn global(mut self, global: bool) -> Self {
+        self.global = global;
+        self
+    }
+
+    /// Marks this flag as informational like --help, required values may be omitted if it's passed
+    pub fn informational(mut self, informational: bool) -> Self {
+        self.informational = informational;
+        self
+    }
+
+    /// Sets the type of the value, it's checked while parsing and shown in the help-text
+    pub fn value_type(mut self, value_type: ValueType) -> Self {
+        if self.completion == Completion::Nothing {
+            self.completion = value_type.completion();
+        }
+
+        self.value_type = value_type;
+        self.expects_value = true;
+        self
+    }
+
+    /// Sets the environment variable this flag falls back to if it isn't passed, it can be
+    /// set in the config-file as well.
+    pub fn env(mut self, name: &str) -> Self {
+        self.env = Option::Some(name.to_string());
+        self
+    }
+
+    /// Forbids passing this flag along with the one named `name`
+    pub fn conflicts_with(mut self, name: &str) -> Self {
+        self.conflicts.push(name.to_string());
+        self
+    }
+
+    /// Allows passing this flag only along with the one named `name`
+    pub fn requires(mut self, name: &str) -> Self {
+        self.requires.push(name.to_string());
+        self
+    }
+
+    /// Adds this flag to a group of which only one flag may be passed at a time
+    pub fn exclusive_group(mut self, group: &str) -> Self {
+        self.group = Option::Some(group.to_string());
+        self
+    }
+
+    /// Sets how often this flag may be passed
+    pub fn multiplicity(mut self, multiplicity: Multiplicity) -> Self {
+        // Only the number of occurrences is kept of counted flags
+        if multiplicity == Multiplicity::Counted && self.expects_value {
+            panic!("Tried to count flag '{}' which expects a value.", self.name)
+        }
+
+        self.multiplicity = multiplicity;
+        self
+    }
+
     /// Sets a description
     pub fn description(mut self, description: &str) -> Self {
         self.description = description.to_string();
         self
     }
 
+    /// Describes the expected value along with its type, e.g. `count: integer`.
+    pub fn value_label(&self) -> String {
+        match (&self.value_type, self.value_description.is_empty()) {
+            (ValueType::Text, _) => self.value_description.clone(),
+            (value_type, true) => value_type.name(),
+            (value_type, false) => format!("{}: {}", self.value_description, value_type.name()),
+        }
+    }
+
     /// Converts this flag to a readable string of how to use it.
     /// Returns a tuple with a usage-string and a clone of the description.
     pub fn stringify(&self) -> (String, String) {
-        let mut usage: String = self.abbr.join(", ");
+        let mut abbr = self.abbr.clone();
+
+        // Show the negation along with the flag it negates, e.g. --[no-]log
+        if let Some(negation) = self.negation() {
+            let long = self.long_abbr().to_string();
+            if let Some(index) = abbr.iter().position(|a| a.eq(&long)) {
+                abbr[index] = negation.replacen("--no-", "--[no-]", 1);
+            }
+        }
+
+        let mut usage: String = abbr.join(", ");
 
         // Add description of expected value if provided
         if self.expects_value {
-            usage.push_str(&format!(" <{}>", self.value_description));
+            usage.push_str(&format!(" <{}>", self.value_label()));
+        }
+
+        // Indicate flags which can be passed multiple times
+        if self.multiplicity != Multiplicity::Once {
+            usage.push_str("...");
         }
 
         (usage, self.description.clone())
     }
 
+    /// Returns the longest abbreviation, e.g. `--dry-run`, used to refer to this flag in errors.
+    /// The first one wins if several are equally long.
+    pub fn long_abbr(&self) -> &str {
+        self.abbr
+            .iter()
+            .rev()
+            .max_by_key(|abbr| abbr.len())
+            .map_or(&self.name, |abbr| abbr)
+    }
+
+    /// Checks whenever this flag is turned on or off instead of taking a value, e.g. `--dry-run`.
+    /// It can be passed as `--dry-run=false` as well.
+    pub fn is_boolean(&self) -> bool {
+        !self.expects_value && self.multiplicity == Multiplicity::Once
+    }
+
+    /// Returns the abbreviation turning this flag off, e.g. `--no-log` for `--log`.
+    /// Only boolean flags with a long abbreviation which can be on without passing them, by
+    /// default or through their environment variable, have one.
+    pub fn negation(&self) -> Option<String> {
+        let long = self.long_abbr().strip_prefix("--")?;
+        match self.is_boolean() && (self.enabled || self.env.is_some()) {
+            true => Option::Some(format!("--no-{}", long)),
+            false => Option::None,
+        }
+    }
+
+    /// Returns all abbreviations including the negation, if there is one.
+    pub fn abbreviations(&self) -> Vec<String> {
+        self.abbr.iter().cloned().chain(self.negation()).collect()
+    }
+
     /// Checks whenever this flag contains a specific abbreviation.
     pub fn has_abbr(&self, other: &str) -> bool {
         self.abbr.contains(&other.to_string())
@@ -139,7 +310,48 @@ mod test {
             .stringify();
 
         assert_eq!(desc, "Hello World");
-        assert_eq!(usage, "-a, --abbr <hello>")
+        assert_eq!(usage, "-a, --abbr <hello>");
+
+        let (usage, _) = CLIFlag::new("Hello")
+            .value_type(ValueType::Size)
+            .abbr("--size")
+            .stringify();
+
+        assert_eq!(usage, "--size <size>")
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_counted_flag() {
+        CLIFlag::new("Hello")
+            .expects_value(true)
+            .multiplicity(Multiplicity::Counted);
+    }
+
+    #[test]
+    fn negation() {
+        let flag = CLIFlag::new("log")
+            .enabled_by_default(true)
+            .abbr("-l")
+            .abbr("--log");
+
+        assert_eq!(flag.negation(), Some(String::from("--no-log")));
+        assert_eq!(flag.abbreviations(), vec!["-l", "--log", "--no-log"]);
+        assert_eq!(flag.stringify().0, "-l, --[no-]log");
+
+        assert_eq!(CLIFlag::new("dry").abbr("--dry").negation(), Option::None);
+        assert_eq!(
+            CLIFlag::new("dry").env("DRY").abbr("--dry").negation(),
+            Some(String::from("--no-dry"))
+        );
+        assert_eq!(
+            CLIFlag::new("v")
+                .env("V")
+                .multiplicity(Multiplicity::Counted)
+                .abbr("-v")
+                .negation(),
+            Option::None
+        );
     }
 
     #[test]
@@ -0,0 +1,244 @@
+use crate::cli::types::ValueType;
+use crate::cli::CLIApp;
+
+/// Escapes text for roff, lines starting with a control character are protected as well.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .lines()
+        .map(
+            |line| match line.starts_with('.') || line.starts_with('\'') {
+                true => format!("\\&{}", line),
+                false => line.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats a list of abbreviations in bold, e.g. `\fB\-d\fR, \fB\-\-dry\fR`.
+fn bold_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("\\fB{}\\fR", escape(item)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl CLIApp {
+    /// Renders a man-page (section 1) in the roff format.
+    pub fn man_page(&self) -> String {
+        let mut page = format!(
+            ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+            escape(&self.name.to_uppercase()),
+            escape(&self.name),
+            escape(&self.version)
+        );
+
+        page.push_str(&format!(
+            ".SH NAME\n{} \\- {}\n",
+            escape(&self.name),
+            escape(&self.about)
+        ));
+
+        // Synopsis, values with a default can be omitted
+        page.push_str(&format!(".SH SYNOPSIS\n.B {}\n", escape(&self.name)));
+        for val in &self.values {
+            let name = format!(
+                "\\fI{}{}\\fR",
+                escape(&val.name),
+                if val.variadic { "..." } else { "" }
+            );
+
+            match val.required && val.default.is_none() {
+                true => page.push_str(&format!("{}\n", name)),
+                false => page.push_str(&format!("[{}]\n", name)),
+            }
+        }
+
+        if !self.flags.is_empty() {
+            page.push_str("[\\fIoptions...\\fR]\n");
+        }
+
+        if !self.subcommands.is_empty() {
+            page.push_str(&format!(
+                ".br\n.B {}\n\\fIcommand\\fR\n[\\fIoptions...\\fR]\n",
+                escape(&self.name)
+            ));
+        }
+
+        if !self.long_description.is_empty() {
+            page.push_str(&format!(
+                ".SH DESCRIPTION\n{}\n",
+                escape(&self.long_description).replace("\n\n", "\n.PP\n")
+            ));
+        }
+
+        // Commands
+        if !self.subcommands.is_empty() {
+            page.push_str(".SH COMMANDS\n");
+        }
+
+        for subcommand in &self.subcommands {
+            page.push_str(&format!(
+                ".TP\n.B {}\n{}\n",
+                escape(&subcommand.name),
+                escape(&subcommand.about)
+            ));
+        }
+
+        // Options
+        if !self.flags.is_empty() {
+            page.push_str(".SH OPTIONS\n");
+        }
+
+        for flag in &self.flags {
+            page.push_str(&format!(".TP\n{}", bold_list(&flag.abbreviations())));
+
+            if flag.expects_value {
+                page.push_str(&format!(" \\fI{}\\fR", escape(&flag.value_label())));
+            }
+
+            let description = flag.description.clone() + &self.constraints_of(flag, &[]);
+            page.push_str(&format!("\n{}\n", escape(&description)));
+        }
+
+        // Arguments
+        if !self.values.is_empty() {
+            page.push_str(".SH ARGUMENTS\n");
+        }
+
+        for val in &self.values {
+            let mut name = val.name.clone() + if val.variadic { "..." } else { "" };
+            if val.value_type != ValueType::Text {
+                name.push_str(&format!(" <{}>", val.value_type.name()));
+            }
+
+            page.push_str(&format!(
+                ".TP\n.I {}\n{}\n",
+                escape(&name),
+                escape(&val.description)
+            ));
+        }
+
+        // Environment variables and the config-file
+        if self.flags.iter().any(|flag| flag.env.is_some()) {
+            page.push_str(".SH ENVIRONMENT\n");
+        }
+
+        for flag in &self.flags {
+            if let Some(env) = &flag.env {
+                page.push_str(&format!(
+                    ".TP\n.B {}\nSame as {}.\n",
+                    escape(env),
+                    bold_list(&[flag.long_abbr().to_string()])
+                ));
+            }
+        }
+
+        let location = match (&self.config_location, &self.config_file) {
+            (Some(location), _) => Option::Some(location.clone()),
+            (None, Some(path)) => Option::Some(path.display().to_string()),
+            (None, None) => Option::None,
+        };
+
+        if let Some(location) = location {
+            page.push_str(&format!(
+                ".SH FILES\n.TP\n.I {}\n{}\n",
+                escape(&location),
+                escape("Sets options which have an environment variable, one \"key = value\" per line where the key is the long flag without dashes. Command-line arguments take precedence over environment variables, which take precedence over this file.")
+            ));
+        }
+
+        // Examples
+        if !self.examples.is_empty() {
+            page.push_str(".SH EXAMPLES\n");
+        }
+
+        for (command, description) in &self.examples {
+            page.push_str(&format!(
+                ".TP\n.B {}\n{}\n",
+                escape(command),
+                escape(description)
+            ));
+        }
+
+        // Exit status
+        if !self.exit_codes.is_empty() {
+            page.push_str(".SH EXIT STATUS\n");
+        }
+
+        for (code, description) in &self.exit_codes {
+            page.push_str(&format!(".TP\n.B {}\n{}\n", code, escape(description)));
+        }
+
+        page
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::cli::flag::CLIFlag;
+    use crate::cli::value::CLIValue;
+    use crate::cli::CLIApp;
+
+    #[test]
+    fn man_page() {
+        let page = CLIApp::new()
+            .name("app")
+            .version("1.0.0")
+            .about("Does things")
+            .long_description("First paragraph.\n\n.Second one.")
+            .example("app --dry", "Does nothing.")
+            .exit_code(2, "Invalid arguments.")
+            .add_flag(
+                CLIFlag::new("dry")
+                    .description("Performs a dry-run.")
+                    .abbr("-d")
+                    .abbr("--dry-run"),
+            )
+            .add_flag(
+                CLIFlag::new("log")
+                    .description("Log-file.")
+                    .env("APP_LOG")
+                    .expects_value(true)
+                    .value_description("file")
+                    .abbr("--log"),
+            )
+            .add_value(CLIValue::new("source").variadic(true).required(true))
+            .add_value(CLIValue::new("target").description("Target."))
+            .add_subcommand(CLIApp::new().name("man").about("Prints this page."))
+            .config_file(Path::new("/etc/app"))
+            .man_page();
+
+        assert!(page.starts_with(
+            ".TH APP 1 \"\" \"app 1.0.0\" \"User Commands\"\n.SH NAME\napp \\- Does things\n"
+        ));
+        assert!(page.contains(
+            ".SH SYNOPSIS\n.B app\n\\fIsource...\\fR\n[\\fItarget\\fR]\n[\\fIoptions...\\fR]\n"
+        ));
+        assert!(page.contains(".br\n.B app\n\\fIcommand\\fR\n[\\fIoptions...\\fR]\n"));
+        assert!(page.contains(".SH COMMANDS\n.TP\n.B man\nPrints this page.\n.SH OPTIONS\n"));
+        assert!(page.contains(".SH DESCRIPTION\nFirst paragraph.\n.PP\n\\&.Second one.\n"));
+        assert!(
+            page.contains(".TP\n\\fB\\-d\\fR, \\fB\\-\\-dry\\-run\\fR\nPerforms a dry\\-run.\n")
+        );
+        assert!(page.contains(".TP\n\\fB\\-\\-log\\fR \\fIfile\\fR\nLog\\-file.\n"));
+        assert!(
+            page.contains(".SH ARGUMENTS\n.TP\n.I source...\nUnknown\n.TP\n.I target\nTarget.\n")
+        );
+        assert!(page.contains(".SH ENVIRONMENT\n.TP\n.B APP_LOG\nSame as \\fB\\-\\-log\\fR.\n"));
+        assert!(page.contains(".SH FILES\n.TP\n.I /etc/app\nSets options"));
+        assert!(page.contains(".SH EXAMPLES\n.TP\n.B app \\-\\-dry\nDoes nothing.\n"));
+        assert!(page.ends_with(".SH EXIT STATUS\n.TP\n.B 2\nInvalid arguments.\n"));
+
+        let page = CLIApp::new()
+            .name("app")
+            .config_file(Path::new("/home/user/.config/app"))
+            .config_location("$XDG_CONFIG_HOME/app")
+            .man_page();
+        assert!(page.contains(".SH FILES\n.TP\n.I $XDG_CONFIG_HOME/app\nSets options"));
+    }
+}
@@ -1,19 +1,45 @@
 use std::collections::HashMap;
+use std::iter::Peekable;
+use std::path::PathBuf;
 
-use flag::CLIFlag;
+use config::Source;
+use flag::{CLIFlag, Multiplicity};
 use result::CLIResult;
+use suggest::did_you_mean;
+use types::{Boolean, ValueType};
 use value::CLIValue;
+use wrap_string::Wrapping;
 
-use crate::lib::Wrapping;
-
+pub mod completion;
+pub mod config;
+pub mod constraints;
 pub mod flag;
+pub mod man;
 pub mod result;
+pub mod suggest;
+pub mod types;
 pub mod value;
+pub mod wrap_string;
+
+/// Resolves a default value based on the values / arguments parsed so far.
+pub type DefaultFn = fn(&HashMap<String, String>) -> String;
+
+/// Validates a value, returns an error-message if it's invalid.
+pub type ValidatorFn = fn(&String) -> Result<(), String>;
 
 pub struct CLIApp {
     name: String,
+    parent: String,
+    version: String,
+    about: String,
+    long_description: String,
+    examples: Vec<(String, String)>,
+    exit_codes: Vec<(i32, String)>,
     flags: Vec<CLIFlag>,
     values: Vec<CLIValue>,
+    subcommands: Vec<CLIApp>,
+    config_file: Option<PathBuf>,
+    config_location: Option<String>,
 }
 
 impl CLIApp {
@@ -21,8 +47,17 @@ impl CLIApp {
     pub fn new() -> Self {
         CLIApp {
             name: String::new(),
+            parent: String::new(),
+            version: String::new(),
+            about: String::new(),
+            long_description: String::new(),
+            examples: Vec::new(),
+            exit_codes: Vec::new(),
             flags: Vec::new(),
             values: Vec::new(),
+            subcommands: Vec::new(),
+            config_file: Option::None,
+            config_location: Option::None,
         }
     }
 
@@ -32,6 +67,37 @@ impl CLIApp {
         self
     }
 
+    /// Sets the version of this app
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    /// Sets a one-line description of what this app does
+    pub fn about(mut self, about: &str) -> Self {
+        self.about = about.to_string();
+        self
+    }
+
+    /// Sets a longer description, paragraphs are separated by an empty line
+    pub fn long_description(mut self, description: &str) -> Self {
+        self.long_description = description.to_string();
+        self
+    }
+
+    /// Adds an example of how to use this app
+    pub fn example(mut self, command: &str, description: &str) -> Self {
+        self.examples
+            .push((command.to_string(), description.to_string()));
+        self
+    }
+
+    /// Documents an exit code
+    pub fn exit_code(mut self, code: i32, description: &str) -> Self {
+        self.exit_codes.push((code, description.to_string()));
+        self
+    }
+
     /// Defines a flag
     /// Panics if name or one of the abbreviations is already in use.
     pub fn add_flag(mut self, new_flag: CLIFlag) -> Self {
@@ -58,8 +124,13 @@ impl CLIApp {
     }
 
     /// Adds a new value
-    /// Panics if the name is already taken
+    /// Panics if the name is already taken or if there's already a variadic value.
     pub fn add_value(mut self, val: CLIValue) -> Self {
+        // Only one variadic value can be distributed unambiguously
+        if val.variadic && self.values.iter().any(|v| v.variadic) {
+            panic!("Value \"{}\" can't be variadic as well.", &val.name)
+        }
+
         // Check if name is already in use
         if self
             .values
@@ -74,6 +145,40 @@ impl CLIApp {
         self
     }
 
+    /// Adds a subcommand with its own flags and values, global flags of this app are inherited.
+    /// The name of this app has to be set before.
+    /// Panics if the name is already taken.
+    pub fn add_subcommand(mut self, mut subcommand: CLIApp) -> Self {
+        if self.subcommands.iter().any(|s| s.name.eq(&subcommand.name)) {
+            panic!(
+                "Subcommand with name \"{}\" is already defined.",
+                &subcommand.name
+            )
+        }
+
+        subcommand.set_parent(&self.full_name());
+        self.subcommands.push(subcommand);
+        self
+    }
+
+    /// Sets the name of the parent, including the one of its parents
+    fn set_parent(&mut self, parent: &str) {
+        self.parent = parent.to_string();
+
+        let full_name = self.full_name();
+        for subcommand in &mut self.subcommands {
+            subcommand.set_parent(&full_name);
+        }
+    }
+
+    /// Returns the name including the ones of all parents, e.g. "cleanup completions"
+    fn full_name(&self) -> String {
+        match self.parent.is_empty() {
+            true => self.name.clone(),
+            false => format!("{} {}", self.parent, self.name),
+        }
+    }
+
     /// Parses the cli-arguments
     pub fn consume_args(&self) -> Result<CLIResult, String> {
         self.consume(std::env::args())
@@ -81,102 +186,143 @@ impl CLIApp {
 
     /// Parses a list of arguments
     pub fn consume<T: Iterator<Item = String>>(&self, raw_args: T) -> Result<CLIResult, String> {
-        let mut args: HashMap<String, String> = HashMap::new();
-        let mut values: HashMap<String, String> = HashMap::new();
-        let mut flags: Vec<String> = Vec::new();
-        let mut value_offset = 0;
-        let max_values = self.values.len();
-
-        // Parse passed values
         let mut iter = raw_args
+            .enumerate() // keep the position for error-messages
             .skip(1) // skip first argument // which is always the executable itself
             .peekable();
 
-        while iter.peek().is_some() {
-            let arg = iter.next().unwrap();
+        let mut result = self.parse(&mut iter, &[])?;
 
-            // If the value starts with a - it's a flag / argument
-            if arg.starts_with("-") {
-                // Check if this is known flag
-                let target_flag = match self.flags.iter().find(|flag| flag.has_abbr(&arg)) {
-                    None => return Err(format!("Unknown flag: {}", arg)),
-                    Some(flag) => flag,
-                };
+        // Environment variables and the config-file only apply if no subcommand is used, their
+        // errors are deferred so flags like --help still work with a broken config-file
+        if result.subcommand().is_none() {
+            if let Err(e) = self.apply_fallbacks(&mut result) {
+                result.defer_error(e);
+            }
+        }
 
-                // Copy name of flag
-                let flag_name = target_flag.name.clone();
-
-                // Parse value of flag if expected
-                if target_flag.expects_value {
-                    let mut had_value = false;
-
-                    // TODO: Refactor
-                    match iter
-                        .peek()
-                        .and_then(|s| {
-                            if s.starts_with("-") {
-                                Option::None
-                            } else {
-                                had_value = true;
-                                Option::Some(s.clone())
-                            }
-                        })
-                        .or(target_flag.resolve_default(&args))
-                    {
-                        None => return Err(format!("Flag {} expects a value.", arg)),
-                        Some(val) => {
-                            // Validate
-                            match target_flag.validator {
-                                None => (),
-                                Some(v) => match v(&val) {
-                                    Ok(_) => (),
-                                    Err(e) => return Err(e),
-                                },
-                            };
-
-                            args.insert(flag_name, val);
-
-                            if had_value {
-                                iter.next();
-                            }
-
-                            continue;
-                        }
-                    };
-                }
+        self.enable_defaults(&mut result);
+        self.check_constraints(&result)?;
+
+        Ok(result)
+    }
+
+    /// Turns on flags of the (sub-)command which matched which are enabled by default, unless
+    /// they've been turned off.
+    fn enable_defaults(&self, result: &mut CLIResult) {
+        let (app, globals) = self.matched(result);
+        let result = result.innermost_mut();
 
-                // Push flag
-                flags.push(flag_name);
+        let flags: Vec<&CLIFlag> = app.flags.iter().chain(globals).collect();
+        for flag in flags.iter().filter(|flag| flag.enabled) {
+            // Defaults never conflict with flags which have been set explicitly
+            let overridden = flags.iter().any(|other| {
+                constraints::conflicting(flag, other) && result.source(&other.name).is_some()
+            });
+
+            if result.source(&flag.name).is_none() && !overridden {
+                result.insert(&flag.name, false, vec![String::new()], Source::Default);
+            }
+        }
+    }
+
+    /// Parses the arguments following the name of this app, `inherited` are the global flags of
+    /// all parents. Each argument comes with its position, used to point to it in errors.
+    fn parse<T: Iterator<Item = (usize, String)>>(
+        &self,
+        iter: &mut Peekable<T>,
+        inherited: &[&CLIFlag],
+    ) -> Result<CLIResult, String> {
+        let mut args: HashMap<String, Vec<String>> = HashMap::new();
+        let mut values: HashMap<String, Vec<String>> = HashMap::new();
+        let mut positional: Vec<(usize, String)> = Vec::new();
+        let mut flags: Vec<String> = Vec::new();
+        let mut negated: Vec<String> = Vec::new();
+        let mut only_values = false;
+        let max_values = self.values.len();
+
+        while let Some((position, arg)) = iter.next() {
+            // Everything following -- is a value, e.g. a directory called like a subcommand
+            if arg == "--" && !only_values {
+                only_values = true;
                 continue;
             }
 
-            // Check if too many values were passed
-            if value_offset == max_values {
-                return Err(format!(
-                    "Too many values. Maximum is {} but got {} as last one.",
-                    max_values, arg
-                ));
+            // If the value starts with a - it's a flag / argument
+            if arg.starts_with('-') && !only_values {
+                self.parse_flag(&arg, iter, inherited, &mut args, &mut flags, &mut negated)
+                    .map_err(|e| format!("Argument {}: {}", position, e))?;
+                continue;
+            }
+
+            // The first value can be the name of a subcommand
+            if positional.is_empty() && !only_values {
+                if let Some(subcommand) = self.subcommands.iter().find(|s| s.name.eq(&arg)) {
+                    let globals: Vec<&CLIFlag> = inherited
+                        .iter()
+                        .copied()
+                        .chain(self.flags.iter().filter(|flag| flag.global))
+                        .collect();
+
+                    let result = subcommand.parse(iter, &globals)?;
+                    return Ok(
+                        CLIResult::from(values, args, flags, negated).with_subcommand(&arg, result)
+                    );
+                }
             }
 
             // Save value
-            values.insert(self.values.get(value_offset).unwrap().name.clone(), arg);
+            positional.push((position, arg));
+        }
 
-            // Increment value pointer
-            value_offset += 1;
+        // Check if too many values were passed
+        if !self.values.iter().any(|v| v.variadic) && positional.len() > max_values {
+            let (position, value) = &positional[max_values];
+            return Err(format!(
+                "Argument {}: Too many values. Maximum is {} but got {} as last one.",
+                position, max_values, value
+            ));
         }
 
+        // Distribute values, a variadic value takes everything not needed by the following ones
+        let mut remaining = positional.into_iter();
+        let mut left = remaining.len();
+        for (index, val) in self.values.iter().enumerate() {
+            if left == 0 {
+                break;
+            }
+
+            let take = match val.variadic {
+                true => (left - 1).saturating_sub(max_values - index - 1) + 1,
+                false => 1,
+            };
+
+            let mut list = Vec::new();
+            for (position, value) in remaining.by_ref().take(take) {
+                // Validate value
+                let valid = match val.value_type.check(&value) {
+                    Err(e) => Err(format!("Invalid value for <{}>: {}", val.name, e)),
+                    Ok(_) => val.validator.map_or(Ok(()), |validator| validator(&value)),
+                };
+
+                valid.map_err(|e| format!("Argument {}: {}", position, e))?;
+                list.push(value);
+            }
+
+            values.insert(val.name.clone(), list);
+            left -= take;
+        }
+
+        // Informational flags like --help work without the values
+        let informational = self
+            .flags
+            .iter()
+            .chain(inherited.iter().copied())
+            .any(|flag| flag.informational && flags.contains(&flag.name));
+
         // Check if values are missing
         for val in &self.values {
             if values.contains_key(&val.name) {
-                // Validate value
-                match val.validator {
-                    None => (),
-                    Some(validator) => match validator(&val.name) {
-                        Err(e) => return Err(e),
-                        Ok(_) => (),
-                    },
-                }
-
                 continue;
             }
 
@@ -184,23 +330,187 @@ impl CLIApp {
             match val.default {
                 None => (),
                 Some(def) => {
-                    values.insert(val.name.clone(), def(&values));
+                    let resolved = def(&CLIResult::first_values(&values));
+                    values.insert(val.name.clone(), vec![resolved]);
                     continue;
                 }
             }
 
             // Check if required but not set
-            if val.required {
+            if val.required && !informational {
                 return Err(format!("Missing value labeled \"{}\"", val.name));
             }
         }
 
-        Ok(CLIResult::from(values, args, flags))
+        Ok(CLIResult::from(values, args, flags, negated))
+    }
+
+    /// Parses a single argument starting with a dash, values not attached to it are taken from `iter`.
+    fn parse_flag<T: Iterator<Item = (usize, String)>>(
+        &self,
+        arg: &str,
+        iter: &mut Peekable<T>,
+        inherited: &[&CLIFlag],
+        args: &mut HashMap<String, Vec<String>>,
+        flags: &mut Vec<String>,
+        negated: &mut Vec<String>,
+    ) -> Result<(), String> {
+        for (flag, attached) in self.split_flags(arg, inherited)? {
+            // Flags which can only be passed once keep the last occurrence, e.g. --log --no-log
+            if flag.multiplicity == Multiplicity::Once {
+                flags.retain(|name| name != &flag.name);
+                negated.retain(|name| name != &flag.name);
+                args.remove(&flag.name);
+            }
+
+            if !flag.expects_value {
+                // Boolean flags can be turned off explicitly, e.g. by --dry-run=no or --no-log
+                let enabled = match attached {
+                    Some(value) => {
+                        if let Err(e) = ValueType::Bool.check(&value) {
+                            return Err(format!("Invalid value for {}: {}", flag.long_abbr(), e));
+                        }
+
+                        value.parse::<Boolean>().is_ok_and(|b| b.0)
+                    }
+                    None => true,
+                };
+
+                match enabled {
+                    true => flags.push(flag.name.clone()),
+                    false => negated.push(flag.name.clone()),
+                }
+
+                continue;
+            }
+
+            // Use the attached value, the following argument or the default
+            let value = match attached {
+                Some(value) => value,
+                None => match iter.next_if(|(_, next)| !next.starts_with('-')) {
+                    Some((_, value)) => value,
+                    None => match flag.resolve_default(&CLIResult::first_values(args)) {
+                        Some(value) => value,
+                        None => return Err(format!("Flag {} expects a value.", arg)),
+                    },
+                },
+            };
+
+            // Validate
+            if let Err(e) = flag.value_type.check(&value) {
+                return Err(format!("Invalid value for {}: {}", flag.long_abbr(), e));
+            }
+
+            if let Some(v) = flag.validator {
+                v(&value)?;
+            }
+
+            args.entry(flag.name.clone()).or_default().push(value);
+        }
+
+        Ok(())
+    }
+
+    /// Splits a single argument into the flags it consists of, along with their attached values.
+    /// Supports `--flag=value`, bundled short flags like `-dl` and attached values like `-ivalue`.
+    fn split_flags<'a>(
+        &'a self,
+        arg: &str,
+        inherited: &[&'a CLIFlag],
+    ) -> Result<Vec<(&'a CLIFlag, Option<String>)>, String> {
+        let find = |abbr: &str| {
+            self.flags
+                .iter()
+                .chain(inherited.iter().copied())
+                .find(|flag| flag.has_abbr(abbr))
+        };
+
+        let negated = |abbr: &str| {
+            self.flags
+                .iter()
+                .chain(inherited.iter().copied())
+                .find(|flag| flag.negation().as_deref() == Some(abbr))
+        };
+
+        let unknown = |abbr: &str| {
+            let candidates: Vec<String> = self
+                .flags
+                .iter()
+                .chain(inherited.iter().copied())
+                .flat_map(|flag| flag.abbreviations())
+                .collect();
+
+            did_you_mean(
+                format!("Unknown flag: {}", abbr),
+                abbr,
+                candidates.iter().map(String::as_str),
+            )
+        };
+
+        if let Some(flag) = find(arg) {
+            return Ok(vec![(flag, Option::None)]);
+        }
+
+        // Turned off explicitly, e.g. --no-log
+        if let Some(flag) = negated(arg) {
+            return Ok(vec![(flag, Option::Some(String::from("false")))]);
+        }
+
+        // Value attached with an equal sign, e.g. --include=txt,pdf or --dry-run=false
+        if let Some((abbr, value)) = arg.split_once('=') {
+            match find(abbr) {
+                Some(flag) if flag.expects_value || flag.is_boolean() => {
+                    return Ok(vec![(flag, Option::Some(value.to_string()))])
+                }
+                Some(_) => return Err(format!("Flag {} doesn't take a value.", abbr)),
+                None if negated(abbr).is_some() => {
+                    return Err(format!("Flag {} doesn't take a value.", abbr))
+                }
+                None if abbr.starts_with("--") => return Err(unknown(abbr)),
+                None => (),
+            }
+        }
+
+        // Bundled short flags, e.g. -dl, only the first one may have a value attached
+        let bundle = match arg.strip_prefix('-') {
+            Some(bundle) if !bundle.is_empty() && !bundle.starts_with('-') => bundle,
+            _ => return Err(unknown(arg)),
+        };
+
+        let short = |c: char| find(&format!("-{}", c));
+        let mut split = Vec::new();
+
+        for (index, c) in bundle.char_indices() {
+            let flag = match short(c) {
+                Some(flag) => flag,
+                None if index == 0 => return Err(unknown(arg)),
+                None => return Err(format!("Unknown flag -{} in {}", c, arg)),
+            };
+
+            let rest = &bundle[index + c.len_utf8()..];
+            if !flag.expects_value || rest.is_empty() {
+                split.push((flag, Option::None));
+                continue;
+            }
+
+            // A value could also be meant as further flags, e.g. -id
+            if index > 0 || rest.chars().all(|c| short(c).is_some()) {
+                return Err(format!(
+                    "Ambiguous flags {}: -{} expects a value, pass it as -{}=<value> or as a separate argument.",
+                    arg, c, c
+                ));
+            }
+
+            split.push((flag, Option::Some(rest.to_string())));
+            break;
+        }
+
+        Ok(split)
     }
 
     /// Creates a usage-description out of the currently defined attributes.
     pub fn usage_description(&self) -> String {
-        let mut desc = format!("Usage: {}", self.name);
+        let mut desc = format!("Usage: {}", self.full_name());
 
         // Push expected values to it
         for val in &self.values {
@@ -214,22 +524,67 @@ impl CLIApp {
             _ => desc.push_str(" [options...]"),
         }
 
+        // Subcommands are an alternative to the values
+        if !self.subcommands.is_empty() {
+            desc.push_str(&format!(
+                "\n       {} <command> [options...]",
+                self.full_name()
+            ));
+        }
+
         desc
     }
 
-    /// Prints a full help-text based on the previously defined attributes.
-    pub fn print_help(&self) {
-        // Print usage description
-        println!("{}", self.usage_description());
+    /// Creates a full help-text based on the previously defined attributes.
+    pub fn help_text(&self) -> String {
+        self.help_text_with(&[])
+    }
+
+    /// Creates the help-text of the (sub-)command which matched, including inherited global flags.
+    pub fn help_for(&self, result: &CLIResult) -> String {
+        let (app, globals) = self.matched(result);
+        app.help_text_with(&globals)
+    }
+
+    /// Returns the (sub-)command which matched along with the global flags it inherited.
+    fn matched(&self, result: &CLIResult) -> (&CLIApp, Vec<&CLIFlag>) {
+        let mut app = self;
+        let mut result = result;
+        let mut globals: Vec<&CLIFlag> = Vec::new();
+
+        while let Some((name, sub_result)) = result.subcommand() {
+            globals.extend(app.flags.iter().filter(|flag| flag.global));
+            app = match app.subcommands.iter().find(|s| s.name.eq(name)) {
+                Some(subcommand) => subcommand,
+                None => break,
+            };
+
+            result = sub_result;
+        }
+
+        (app, globals)
+    }
+
+    /// Creates a help-text listing `globals` along with the own flags.
+    fn help_text_with(&self, globals: &[&CLIFlag]) -> String {
+        // Usage description
+        let mut help = format!("{}\n", self.usage_description());
 
         // Prepare flags for printing and aligning them
         let mut longest_left_side: usize = 0;
         let mut values_map: Vec<(String, String)> = Vec::new();
         let mut flag_map: Vec<(String, String)> = Vec::new();
         let mut arg_map: Vec<(String, String)> = Vec::new();
+        let mut command_map: Vec<(String, String)> = Vec::new();
 
-        for flag in &self.flags {
-            let (usage, desc) = flag.stringify();
+        for subcommand in &self.subcommands {
+            longest_left_side = longest_left_side.max(subcommand.name.len());
+            command_map.push((subcommand.about.clone(), subcommand.name.clone()));
+        }
+
+        for flag in self.flags.iter().chain(globals.iter().copied()) {
+            let (usage, mut desc) = flag.stringify();
+            desc.push_str(&self.constraints_of(flag, globals));
 
             // Update the maximum length of the command-syntax
             // This will be used to properly pad and align the commands later
@@ -259,37 +614,47 @@ impl CLIApp {
 
         // Print flags
         let sections = [
+            ("Commands:", &command_map),
             ("Flags:", &flag_map),
             ("Arguments:", &arg_map),
             ("Values:", &values_map),
         ];
 
         for (section_name, content) in sections.iter() {
-            if content.len() == 0 {
+            if content.is_empty() {
                 continue;
             }
 
-            println!("\n{}", section_name);
+            help.push_str(&format!("\n{}\n", section_name));
             for (name, flags) in content.iter() {
-                println!("  {: <width$}  {}", flags, name, width = longest_left_side);
+                help.push_str(&format!(
+                    "  {: <width$}  {}\n",
+                    flags,
+                    name,
+                    width = longest_left_side
+                ));
             }
         }
+
+        help
+    }
+
+    /// Prints a full help-text based on the previously defined attributes.
+    pub fn print_help(&self) {
+        print!("{}", self.help_text());
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::cli::flag::CLIFlag;
+    use crate::cli::types::{ByteSize, ValueType};
     use crate::cli::value::CLIValue;
     use crate::cli::*;
 
     macro_rules! create_args {
         ($($element: expr), *) => {
-            {
-                let mut v = Vec::new();
-                $( v.push(String::from($element)); )*
-                v.into_iter()
-            }
+            vec![$( String::from($element) ),*].into_iter()
         };
     }
 
@@ -377,4 +742,342 @@ mod test {
             .consume(create_args!("", "hello", "world", "bam"))
             .is_err());
     }
+
+    #[test]
+    fn variadic_values() {
+        let app = CLIApp::new()
+            .name("hello-world")
+            .add_value(
+                CLIValue::new("source")
+                    .variadic(true)
+                    .default(|_| String::from(".")),
+            )
+            .add_value(CLIValue::new("target"));
+
+        let p1 = app.consume(create_args!("")).unwrap();
+        assert_eq!(p1.get_values("source"), ["."]);
+        assert!(!p1.has_value("target"));
+
+        let p2 = app.consume(create_args!("", "a", "b")).unwrap();
+        assert_eq!(p2.get_values("source"), ["a"]);
+        assert_eq!(p2.get_value("target").unwrap(), "b");
+
+        let p3 = app.consume(create_args!("", "a", "b", "c", "d")).unwrap();
+        assert_eq!(p3.get_values("source"), ["a", "b", "c"]);
+        assert_eq!(p3.get_value("source").unwrap(), "a");
+        assert_eq!(p3.get_value("target").unwrap(), "d");
+    }
+
+    #[test]
+    #[should_panic]
+    fn fail_on_multiple_variadic_values() {
+        CLIApp::new()
+            .add_value(CLIValue::new("a").variadic(true))
+            .add_value(CLIValue::new("b").variadic(true));
+    }
+
+    #[test]
+    fn attached_values_and_bundles() {
+        let app = CLIApp::new()
+            .add_flag(CLIFlag::new("dry").abbr("-d").abbr("--dry"))
+            .add_flag(CLIFlag::new("all").abbr("-a"))
+            .add_flag(
+                CLIFlag::new("include")
+                    .expects_value(true)
+                    .abbr("-i")
+                    .abbr("--include"),
+            );
+
+        let p1 = app
+            .consume(create_args!("", "--include=txt,pdf=x", "-da"))
+            .unwrap();
+        assert_eq!(p1.get_arg("include").unwrap(), "txt,pdf=x");
+        assert!(p1.has_flag("dry"));
+        assert!(p1.has_flag("all"));
+
+        let p2 = app.consume(create_args!("", "-ipdf")).unwrap();
+        assert_eq!(p2.get_arg("include").unwrap(), "pdf");
+
+        let p3 = app.consume(create_args!("", "-dai", "txt")).unwrap();
+        assert!(p3.has_flag("all"));
+        assert_eq!(p3.get_arg("include").unwrap(), "txt");
+
+        let p4 = app.consume(create_args!("", "-i=-x")).unwrap();
+        assert_eq!(p4.get_arg("include").unwrap(), "-x");
+
+        let error = |args: Vec<&str>| {
+            app.consume(args.into_iter().map(String::from))
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(
+            error(vec!["", "--dry=maybe"]),
+            "Argument 1: Invalid value for --dry: expected a boolean like yes or no but got maybe"
+        );
+        assert_eq!(
+            error(vec!["", "--foo=bar"]),
+            "Argument 1: Unknown flag: --foo"
+        );
+        assert_eq!(error(vec!["", "-dx"]), "Argument 1: Unknown flag -x in -dx");
+        assert!(
+            error(vec!["", "-dipdf"]).starts_with("Argument 1: Ambiguous flags -dipdf: -i expects")
+        );
+        assert!(error(vec!["", "-ida"]).starts_with("Argument 1: Ambiguous flags -ida"));
+    }
+
+    #[test]
+    fn multiplicity() {
+        let app = CLIApp::new()
+            .add_flag(
+                CLIFlag::new("verbose")
+                    .multiplicity(Multiplicity::Counted)
+                    .abbr("-v"),
+            )
+            .add_flag(
+                CLIFlag::new("include")
+                    .expects_value(true)
+                    .multiplicity(Multiplicity::Repeated)
+                    .abbr("-i"),
+            )
+            .add_flag(CLIFlag::new("target").expects_value(true).abbr("-t"));
+
+        let p1 = app
+            .consume(create_args!(
+                "", "-vvv", "-i", "a,b", "-ic", "-v", "-t", "x"
+            ))
+            .unwrap();
+        assert_eq!(p1.count("verbose"), 4);
+        assert!(p1.has_flag("verbose"));
+        assert_eq!(p1.get_args("include"), ["a,b", "c"]);
+        assert_eq!(p1.get_arg("include").unwrap(), "c");
+        assert_eq!(p1.get_args("target"), ["x"]);
+
+        let p2 = app.consume(create_args!("")).unwrap();
+        assert_eq!(p2.count("verbose"), 0);
+        assert!(p2.get_args("include").is_empty());
+
+        let p3 = app.consume(create_args!("", "-t", "x", "-t", "y")).unwrap();
+        assert_eq!(p3.get_args("target"), ["y"]);
+    }
+
+    #[test]
+    fn boolean_flags() {
+        let app = CLIApp::new()
+            .add_flag(
+                CLIFlag::new("dry")
+                    .env("CLI_TEST_DRY")
+                    .abbr("-d")
+                    .abbr("--dry"),
+            )
+            .add_flag(
+                CLIFlag::new("log")
+                    .enabled_by_default(true)
+                    .abbr("-l")
+                    .abbr("--log"),
+            )
+            .add_flag(
+                CLIFlag::new("verbose")
+                    .multiplicity(Multiplicity::Counted)
+                    .abbr("-v")
+                    .abbr("--verbose"),
+            );
+
+        let consume = |args: Vec<&str>| app.consume(args.into_iter().map(String::from));
+
+        let p1 = consume(vec![""]).unwrap();
+        assert!(p1.has_flag("log"));
+        assert_eq!(p1.source("log"), Some(&Source::Default));
+        assert!(!p1.has_flag("dry"));
+
+        let p2 = consume(vec!["", "--no-log", "-d=yes"]).unwrap();
+        assert!(!p2.has_flag("log"));
+        assert_eq!(p2.source("log"), Some(&Source::CommandLine));
+        assert!(p2.has_flag("dry"));
+
+        let p3 = consume(vec!["", "--log=off"]).unwrap();
+        assert!(!p3.has_flag("log"));
+
+        // The last one wins if a flag is passed multiple times
+        assert!(!consume(vec!["", "--log", "--no-log"])
+            .unwrap()
+            .has_flag("log"));
+        assert!(consume(vec!["", "-d", "--no-dry", "-d"])
+            .unwrap()
+            .has_flag("dry"));
+
+        // Turning a flag off takes precedence over its environment variable
+        std::env::set_var("CLI_TEST_DRY", "1");
+        assert!(consume(vec![""]).unwrap().has_flag("dry"));
+        assert!(!consume(vec!["", "--no-dry"]).unwrap().has_flag("dry"));
+        std::env::remove_var("CLI_TEST_DRY");
+
+        let error = |args: Vec<&str>| consume(args).err().unwrap();
+        assert_eq!(
+            error(vec!["", "--no-log=yes"]),
+            "Argument 1: Flag --no-log doesn't take a value."
+        );
+        assert_eq!(
+            error(vec!["", "--verbose=2"]),
+            "Argument 1: Flag --verbose doesn't take a value."
+        );
+        assert_eq!(
+            error(vec!["", "--no-lgo"]),
+            "Argument 1: Unknown flag: --no-lgo, did you mean --no-log?"
+        );
+        assert_eq!(
+            error(vec!["", "--no-verbose"]),
+            "Argument 1: Unknown flag: --no-verbose, did you mean --verbose?"
+        );
+    }
+
+    #[test]
+    fn typed_values() {
+        let app = CLIApp::new()
+            .add_flag(
+                CLIFlag::new("size")
+                    .value_type(ValueType::Size)
+                    .multiplicity(Multiplicity::Repeated)
+                    .abbr("--size"),
+            )
+            .add_value(CLIValue::new("count").value_type(ValueType::Integer));
+
+        let p1 = app
+            .consume(create_args!("", "--size", "1K", "--size=2", "3"))
+            .unwrap();
+        assert_eq!(p1.get("size"), Some(ByteSize(2)));
+        assert_eq!(
+            p1.get_all::<ByteSize>("size"),
+            [ByteSize(1024), ByteSize(2)]
+        );
+        assert_eq!(p1.get::<u32>("count"), Some(3));
+        assert_eq!(p1.get::<u32>("missing"), Option::None);
+
+        assert_eq!(
+            app.consume(create_args!("", "--size", "big"))
+                .err()
+                .unwrap(),
+            "Argument 1: Invalid value for --size: expected a size like 10M but got big"
+        );
+        assert_eq!(
+            app.consume(create_args!("", "x")).err().unwrap(),
+            "Argument 1: Invalid value for <count>: expected an integer but got x"
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let app = CLIApp::new()
+            .add_flag(CLIFlag::new("exclude").abbr("-e").abbr("--exclude"))
+            .add_value(CLIValue::new("shell").value_type(ValueType::one_of(&["bash", "zsh"])))
+            .add_value(CLIValue::new("target"));
+
+        let error = |args: Vec<&str>| {
+            app.consume(args.into_iter().map(String::from))
+                .err()
+                .unwrap()
+        };
+
+        assert_eq!(
+            error(vec!["", "bash", "--exlude"]),
+            "Argument 2: Unknown flag: --exlude, did you mean --exclude?"
+        );
+        assert_eq!(
+            error(vec!["", "--exclud=x"]),
+            "Argument 1: Unknown flag: --exclud, did you mean --exclude?"
+        );
+        assert_eq!(error(vec!["", "--foo"]), "Argument 1: Unknown flag: --foo");
+        assert_eq!(
+            error(vec!["", "-e", "zhs"]),
+            "Argument 2: Invalid value for <shell>: expected one of bash, zsh but got zhs, did you mean zsh?"
+        );
+        assert_eq!(
+            error(vec!["", "zsh", "a", "b"]),
+            "Argument 3: Too many values. Maximum is 2 but got b as last one."
+        );
+    }
+
+    fn subcommands() -> CLIApp {
+        CLIApp::new()
+            .name("app")
+            .add_flag(
+                CLIFlag::new("help")
+                    .global(true)
+                    .informational(true)
+                    .abbr("-h"),
+            )
+            .add_flag(CLIFlag::new("dry").abbr("-d"))
+            .add_value(CLIValue::new("source"))
+            .add_subcommand(
+                CLIApp::new()
+                    .name("print")
+                    .about("Prints something.")
+                    .add_flag(CLIFlag::new("loud").abbr("-l"))
+                    .add_value(CLIValue::new("text")),
+            )
+            .add_subcommand(
+                CLIApp::new()
+                    .name("say")
+                    .add_value(CLIValue::new("text").required(true)),
+            )
+    }
+
+    #[test]
+    fn subcommand() {
+        let app = subcommands();
+
+        let p1 = app
+            .consume(create_args!("", "-h", "print", "-l", "hi"))
+            .unwrap();
+        let (name, sub) = p1.subcommand().unwrap();
+        assert_eq!(name, "print");
+        assert!(sub.has_flag("help"));
+        assert!(sub.has_flag("loud"));
+        assert_eq!(sub.get_value("text").unwrap(), "hi");
+        assert!(p1.innermost().has_flag("loud"));
+
+        // Values following -- are never subcommands or flags
+        let separated = app.consume(create_args!("", "--", "print")).unwrap();
+        assert!(separated.subcommand().is_none());
+        assert_eq!(separated.get_value("source").unwrap(), "print");
+
+        let literal = app.consume(create_args!("", "-d", "--", "-h")).unwrap();
+        assert!(literal.has_flag("dry"));
+        assert!(!literal.has_flag("help"));
+        assert_eq!(literal.get_value("source").unwrap(), "-h");
+
+        // Global flags are inherited, others aren't
+        let p2 = app.consume(create_args!("", "print", "-h")).unwrap();
+        assert!(p2.innermost().has_flag("help"));
+        assert!(app.consume(create_args!("", "print", "-d")).is_err());
+
+        // The name of a subcommand is only special as first value
+        let p3 = app.consume(create_args!("", "src")).unwrap();
+        assert!(p3.subcommand().is_none());
+        assert_eq!(p3.get_value("source").unwrap(), "src");
+
+        // Required values may be omitted if an informational flag is passed
+        assert!(app.consume(create_args!("", "say")).is_err());
+        assert!(app.consume(create_args!("", "say", "-h")).is_ok());
+    }
+
+    #[test]
+    fn subcommand_help() {
+        let app = subcommands();
+        assert!(app.help_text().contains("Commands:\n  print"));
+
+        let result = app.consume(create_args!("", "print", "-h")).unwrap();
+        let help = app.help_for(&result);
+        assert!(help.starts_with("Usage: app print <text?> [options]\n"));
+        assert!(help.contains("-h"));
+        assert!(!help.contains("-d"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn fail_on_duplicate_subcommands() {
+        CLIApp::new()
+            .add_subcommand(CLIApp::new().name("a"))
+            .add_subcommand(CLIApp::new().name("a"));
+    }
 }
@@ -1,22 +1,128 @@
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::cli::config::Source;
 
 pub struct CLIResult {
-    values: HashMap<String, String>,
-    args: HashMap<String, String>,
+    values: HashMap<String, Vec<String>>,
+    args: HashMap<String, Vec<String>>,
     flags: Vec<String>,
+    sources: HashMap<String, Source>,
+    subcommand: Option<(String, Box<CLIResult>)>,
+    deferred_error: Option<String>,
 }
 
 impl CLIResult {
     pub fn from(
-        values: HashMap<String, String>,
-        args: HashMap<String, String>,
+        values: HashMap<String, Vec<String>>,
+        args: HashMap<String, Vec<String>>,
         flags: Vec<String>,
+        negated: Vec<String>,
     ) -> CLIResult {
+        // Flags which have been turned off keep their source, they don't fall back to anything
+        let sources = args
+            .keys()
+            .chain(flags.iter())
+            .chain(negated.iter())
+            .map(|name| (name.clone(), Source::CommandLine))
+            .collect();
+
         CLIResult {
             values,
             args,
             flags,
+            sources,
+            subcommand: Option::None,
+            deferred_error: Option::None,
+        }
+    }
+
+    /// Sets the result of a matched subcommand.
+    /// Global flags passed before the subcommand are copied into its result.
+    pub fn with_subcommand(mut self, name: &str, mut result: CLIResult) -> CLIResult {
+        for (name, value) in &self.args {
+            result
+                .args
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+
+        for (name, source) in &self.sources {
+            result
+                .sources
+                .entry(name.clone())
+                .or_insert_with(|| source.clone());
+        }
+
+        result.flags.extend(self.flags.iter().cloned());
+        self.subcommand = Option::Some((name.to_string(), Box::new(result)));
+        self
+    }
+
+    /// Returns the name and result of the subcommand which matched, if any.
+    pub fn subcommand(&self) -> Option<(&str, &CLIResult)> {
+        self.subcommand
+            .as_ref()
+            .map(|(name, result)| (name.as_str(), result.as_ref()))
+    }
+
+    /// Returns the result of the most nested subcommand which matched, or this one.
+    /// It contains the global flags of all parents.
+    pub fn innermost(&self) -> &CLIResult {
+        match &self.subcommand {
+            Some((_, result)) => result.innermost(),
+            None => self,
+        }
+    }
+
+    /// Same as [`CLIResult::innermost`] but mutable.
+    pub fn innermost_mut(&mut self) -> &mut CLIResult {
+        if self.subcommand.is_none() {
+            return self;
         }
+
+        match &mut self.subcommand {
+            Some((_, result)) => result.innermost_mut(),
+            None => unreachable!(),
+        }
+    }
+
+    /// Keeps an error of the environment or config-file, see [`CLIResult::deferred_error`].
+    pub fn defer_error(&mut self, error: String) {
+        self.deferred_error = Option::Some(error);
+    }
+
+    /// Returns the error which occurred while reading the environment or config-file, if any.
+    /// It has to be checked once flags which work regardless of it, e.g. `--help`, are handled.
+    pub fn deferred_error(&self) -> Option<&String> {
+        self.deferred_error.as_ref()
+    }
+
+    /// Returns where a flag or argument comes from, `Option::None` if it hasn't been set.
+    pub fn source(&self, name: &str) -> Option<&Source> {
+        self.sources.get(name)
+    }
+
+    /// Adds a flag (set once per entry of `values`) or argument which hasn't been passed as
+    /// cli-argument.
+    pub fn insert(&mut self, name: &str, expects_value: bool, values: Vec<String>, source: Source) {
+        match expects_value {
+            true => self
+                .args
+                .entry(name.to_string())
+                .or_default()
+                .extend(values),
+            false => self.flags.extend(values.iter().map(|_| name.to_string())),
+        }
+
+        self.sources.insert(name.to_string(), source);
+    }
+
+    /// Removes a flag or argument.
+    pub fn remove(&mut self, name: &str) {
+        self.args.remove(name);
+        self.flags.retain(|flag| flag != name);
+        self.sources.remove(name);
     }
 
     /// Checks whenever a flag is set.
@@ -29,9 +135,22 @@ impl CLIResult {
         self.args.contains_key(name)
     }
 
-    /// Resolves an argument.
+    /// Resolves an argument, the last one if it's been passed multiple times.
     pub fn get_arg(&self, name: &str) -> Option<&String> {
-        self.args.get(name)
+        self.args.get(name).and_then(|list| list.last())
+    }
+
+    /// Resolves all values passed for a repeatable argument.
+    pub fn get_args(&self, name: &str) -> &[String] {
+        self.args.get(name).map_or(&[], |list| list.as_slice())
+    }
+
+    /// Counts how often a flag is set, e.g. 3 for `-vvv`.
+    pub fn count(&self, name: &str) -> usize {
+        self.flags
+            .iter()
+            .filter(|flag| flag.as_str() == name)
+            .count()
     }
 
     /// Checks whenever a value exist.
@@ -39,8 +158,39 @@ impl CLIResult {
         self.values.contains_key(name)
     }
 
-    /// Resolves a value.
+    /// Resolves a value, the first one if the value is variadic.
     pub fn get_value(&self, name: &str) -> Option<&String> {
-        self.values.get(name)
+        self.values.get(name).and_then(|list| list.first())
+    }
+
+    /// Resolves all values passed for a variadic value.
+    pub fn get_values(&self, name: &str) -> &[String] {
+        self.values.get(name).map_or(&[], |list| list.as_slice())
+    }
+
+    /// Parses an argument or value, the last one if passed multiple times.
+    /// Returns `Option::None` if it isn't set or can't be parsed.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get_arg(name)
+            .or_else(|| self.get_values(name).last())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Parses all entries of a repeatable argument or variadic value, skipping invalid ones.
+    pub fn get_all<T: FromStr>(&self, name: &str) -> Vec<T> {
+        let list = match self.args.get(name) {
+            Some(list) => list.as_slice(),
+            None => self.get_values(name),
+        };
+
+        list.iter().filter_map(|value| value.parse().ok()).collect()
+    }
+
+    /// Reduces a map of values or arguments to their first entries, e.g. for resolving defaults.
+    pub fn first_values(values: &HashMap<String, Vec<String>>) -> HashMap<String, String> {
+        values
+            .iter()
+            .filter_map(|(name, list)| Some((name.clone(), list.first()?.clone())))
+            .collect()
     }
 }
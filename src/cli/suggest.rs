@@ -0,0 +1,91 @@
+/// Computes the edit-distance between two strings, e.g. how many characters have to be inserted,
+/// removed, replaced or swapped with their neighbour to turn one into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j - 1] + cost)
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+
+            // Swapped characters, e.g. "zhs" instead of "zsh"
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+/// Returns the candidate closest to `input`, if it's close enough to be a likely typo.
+/// Inputs of up to two characters, e.g. `-x`, are one edit away from almost any short flag.
+pub fn closest<'a, I: IntoIterator<Item = &'a str>>(input: &str, candidates: I) -> Option<&'a str> {
+    let length = input.chars().count();
+    if length <= 2 {
+        return Option::None;
+    }
+
+    let max_distance = length / 3;
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Appends a suggestion to an error-message, e.g. `Unknown flag: --exlude, did you mean --exclude?`.
+pub fn did_you_mean<'a, I: IntoIterator<Item = &'a str>>(
+    message: String,
+    input: &str,
+    candidates: I,
+) -> String {
+    match closest(input, candidates) {
+        Some(candidate) => format!("{}, did you mean {}?", message, candidate),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::suggest::*;
+
+    #[test]
+    fn distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("--exlude", "--exclude"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("zsh", "zsh"), 0);
+        assert_eq!(edit_distance("zhs", "zsh"), 1);
+    }
+
+    #[test]
+    fn suggestions() {
+        let flags = vec!["-e", "--exclude", "--include", "--dry-run"];
+        assert_eq!(closest("--exlude", flags.clone()), Some("--exclude"));
+        assert_eq!(closest("--dryrun", flags.clone()), Some("--dry-run"));
+        assert_eq!(closest("--foo", flags.clone()), Option::None);
+        assert_eq!(closest("-x", flags.clone()), Option::None);
+        assert_eq!(closest("", flags.clone()), Option::None);
+        assert_eq!(
+            did_you_mean(String::from("Unknown shell"), "bsh", vec!["bash", "zsh"]),
+            "Unknown shell, did you mean bash?"
+        );
+    }
+}
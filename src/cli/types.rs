@@ -0,0 +1,247 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use path_absolutize::Absolutize;
+
+use crate::cli::completion::Completion;
+use crate::cli::suggest::did_you_mean;
+
+/// Type of the value of a flag or a value, checked while parsing and shown in the help-text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    /// Any text.
+    Text,
+
+    /// A [`Boolean`], e.g. `yes` or `false`.
+    Bool,
+
+    /// A whole, non-negative number.
+    Integer,
+
+    /// A [`ByteSize`], e.g. `10M`.
+    Size,
+
+    /// A [`TimeSpan`], e.g. `7d`.
+    Duration,
+
+    /// An [`AbsolutePath`].
+    Path,
+
+    /// One of a fixed set of words.
+    Enum(Vec<String>),
+}
+
+impl ValueType {
+    /// Creates an enum-type out of the allowed words.
+    pub fn one_of(words: &[&str]) -> Self {
+        ValueType::Enum(words.iter().map(|s| s.to_string()).collect())
+    }
+
+    /// Name shown in the help-text, e.g. `size` or `bash|zsh|fish`.
+    pub fn name(&self) -> String {
+        match self {
+            ValueType::Text => String::from("text"),
+            ValueType::Bool => String::from("boolean"),
+            ValueType::Integer => String::from("integer"),
+            ValueType::Size => String::from("size"),
+            ValueType::Duration => String::from("duration"),
+            ValueType::Path => String::from("path"),
+            ValueType::Enum(words) => words.join("|"),
+        }
+    }
+
+    /// What shell-completions suggest for values of this type by default.
+    pub fn completion(&self) -> Completion {
+        match self {
+            ValueType::Path => Completion::Files,
+            ValueType::Enum(words) => Completion::Words(words.clone()),
+            _ => Completion::Nothing,
+        }
+    }
+
+    /// Checks whenever a value can be parsed as this type, returns a readable error if not.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let (valid, expected) = match self {
+            ValueType::Text => (true, String::new()),
+            ValueType::Bool => (
+                value.parse::<Boolean>().is_ok(),
+                String::from("a boolean like yes or no"),
+            ),
+            ValueType::Integer => (value.parse::<u64>().is_ok(), String::from("an integer")),
+            ValueType::Size => (
+                value.parse::<ByteSize>().is_ok(),
+                String::from("a size like 10M"),
+            ),
+            ValueType::Duration => (
+                value.parse::<TimeSpan>().is_ok(),
+                String::from("a duration with a unit like 7d"),
+            ),
+            ValueType::Path => (
+                value.parse::<AbsolutePath>().is_ok(),
+                String::from("a path"),
+            ),
+            ValueType::Enum(words) => (
+                words.iter().any(|word| word == value),
+                format!("one of {}", words.join(", ")),
+            ),
+        };
+
+        let message = format!("expected {} but got {}", expected, value);
+        match (valid, self) {
+            (true, _) => Ok(()),
+            (false, ValueType::Enum(words)) => Err(did_you_mean(
+                message,
+                value,
+                words.iter().map(String::as_str),
+            )),
+            (false, _) => Err(message),
+        }
+    }
+}
+
+/// A boolean which also accepts `yes` / `no`, `on` / `off` and `1` / `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boolean(pub bool);
+
+impl FromStr for Boolean {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Boolean(true)),
+            "false" | "no" | "off" | "0" => Ok(Boolean(false)),
+            _ => Err(format!("Invalid boolean: {}", s)),
+        }
+    }
+}
+
+/// An amount of bytes with an optional binary unit, e.g. `512`, `10K`, `1.5G` or `2TiB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let number = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let factor: u64 = match &lower[number.len()..] {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => 1 << 10,
+            "m" | "mb" | "mib" => 1 << 20,
+            "g" | "gb" | "gib" => 1 << 30,
+            "t" | "tb" | "tib" => 1 << 40,
+            _ => return Err(format!("Invalid size: {}", s)),
+        };
+
+        match number.parse::<f64>() {
+            Ok(n) if n >= 0.0 && n.is_finite() => Ok(ByteSize((n * factor as f64) as u64)),
+            _ => Err(format!("Invalid size: {}", s)),
+        }
+    }
+}
+
+/// A duration with a unit, e.g. `30s`, `15m`, `12h`, `7d` or `2w`.
+/// Plain numbers are rejected as they could be meant as seconds or as days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSpan(pub Duration);
+
+impl FromStr for TimeSpan {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let number = trimmed.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let factor: u64 = match &trimmed[number.len()..] {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(format!("Invalid duration: {}", s)),
+        };
+
+        match number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(factor))
+        {
+            Some(secs) => Ok(TimeSpan(Duration::from_secs(secs))),
+            None => Err(format!("Invalid duration: {}", s)),
+        }
+    }
+}
+
+/// A path made absolute based on the current working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsolutePath(pub PathBuf);
+
+impl FromStr for AbsolutePath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(String::from("Empty path"));
+        }
+
+        match PathBuf::from(s).absolutize() {
+            Ok(path) => Ok(AbsolutePath(path)),
+            Err(e) => Err(format!("Invalid path {} ({})", s, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::types::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("Yes".parse(), Ok(Boolean(true)));
+        assert_eq!("off".parse(), Ok(Boolean(false)));
+        assert!("maybe".parse::<Boolean>().is_err());
+
+        assert_eq!("512".parse(), Ok(ByteSize(512)));
+        assert_eq!("10M".parse(), Ok(ByteSize(10 * 1024 * 1024)));
+        assert_eq!("1.5kib".parse(), Ok(ByteSize(1536)));
+        assert!("10X".parse::<ByteSize>().is_err());
+        assert!("-1".parse::<ByteSize>().is_err());
+
+        assert_eq!("90s".parse(), Ok(TimeSpan(Duration::from_secs(90))));
+        assert!("90".parse::<TimeSpan>().is_err());
+        assert_eq!("7d".parse(), Ok(TimeSpan(Duration::from_secs(604_800))));
+        assert_eq!("2w".parse(), Ok(TimeSpan(Duration::from_secs(1_209_600))));
+        assert!("7y".parse::<TimeSpan>().is_err());
+
+        assert!("a".parse::<AbsolutePath>().unwrap().0.is_absolute());
+        assert!("".parse::<AbsolutePath>().is_err());
+    }
+
+    #[test]
+    fn check() {
+        let shell = ValueType::one_of(&["bash", "zsh"]);
+        assert_eq!(shell.name(), "bash|zsh");
+        assert!(shell.check("zsh").is_ok());
+        assert_eq!(
+            shell.check("fish"),
+            Err(String::from("expected one of bash, zsh but got fish"))
+        );
+        assert_eq!(
+            shell.check("zhs"),
+            Err(String::from(
+                "expected one of bash, zsh but got zhs, did you mean zsh?"
+            ))
+        );
+
+        assert!(ValueType::Integer.check("3").is_ok());
+        assert_eq!(
+            ValueType::Integer.check("-3"),
+            Err(String::from("expected an integer but got -3"))
+        );
+        assert_eq!(
+            ValueType::Size.check("x"),
+            Err(String::from("expected a size like 10M but got x"))
+        );
+    }
+}
@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use crate::cli::completion::Completion;
+use crate::cli::types::ValueType;
+use crate::cli::{DefaultFn, ValidatorFn};
 
 /// A CLIValue represents a stand-alone value passed without and flag prepended
 pub struct CLIValue {
     pub name: String,
-    pub default: Option<fn(&HashMap<String, String>) -> String>,
+    pub default: Option<DefaultFn>,
     pub required: bool,
+    pub variadic: bool,
     pub description: String,
-    pub validator: Option<fn(&String) -> Result<(), String>>,
+    pub validator: Option<ValidatorFn>,
+    pub completion: Completion,
+    pub value_type: ValueType,
 }
 
 impl CLIValue {
@@ -16,13 +21,16 @@ impl CLIValue {
             name: name.to_owned(),
             default: Option::None,
             required: false,
+            variadic: false,
             description: String::from("Unknown"),
             validator: Option::None,
+            completion: Completion::Nothing,
+            value_type: ValueType::Text,
         }
     }
 
     /// Sets a default value
-    pub fn default(mut self, default: fn(&HashMap<String, String>) -> String) -> Self {
+    pub fn default(mut self, default: DefaultFn) -> Self {
         self.default = Option::Some(default);
         self.required = true;
         self
@@ -34,12 +42,34 @@ impl CLIValue {
         self
     }
 
+    /// Lets this value take multiple entries
+    pub fn variadic(mut self, variadic: bool) -> Self {
+        self.variadic = variadic;
+        self
+    }
+
     /// Sets a validator for this value
-    pub fn validate(mut self, validator: fn(&String) -> Result<(), String>) -> Self {
+    pub fn validate(mut self, validator: ValidatorFn) -> Self {
         self.validator = Option::Some(validator);
         self
     }
 
+    /// Sets what shell-completions suggest for this one
+    pub fn complete(mut self, completion: Completion) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    /// Sets the type, it's checked while parsing and shown in the help-text
+    pub fn value_type(mut self, value_type: ValueType) -> Self {
+        if self.completion == Completion::Nothing {
+            self.completion = value_type.completion();
+        }
+
+        self.value_type = value_type;
+        self
+    }
+
     /// Sets a description
     pub fn description(mut self, description: &str) -> Self {
         self.description = description.to_string();
@@ -48,11 +78,16 @@ impl CLIValue {
 
     /// Returns the description and name as tuple
     /// The name name will get a "?" as postfix if this value is marked as optional
-    /// e.g. not required.
+    /// e.g. not required, a "..." if it's variadic and its type if there is one.
     pub fn stringify(&self) -> (String, String) {
-        (
-            self.name.clone() + if self.required { "" } else { "?" },
-            self.description.clone(),
-        )
+        let mut name = self.name.clone()
+            + if self.variadic { "..." } else { "" }
+            + if self.required { "" } else { "?" };
+
+        if self.value_type != ValueType::Text {
+            name.push_str(&format!(": {}", self.value_type.name()));
+        }
+
+        (name, self.description.clone())
     }
 }
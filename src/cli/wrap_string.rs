@@ -18,7 +18,7 @@ impl Wrapping for String {
         if self.is_wrapped(pat) {
             self.clone()
         } else {
-            vec![pat, self, pat].concat()
+            [pat, self, pat].concat()
         }
     }
 
@@ -26,14 +26,14 @@ impl Wrapping for String {
         if self.is_wrapped_in(start, end) {
             self.clone()
         } else {
-            vec![start, self, end].concat()
+            [start, self, end].concat()
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::lib::*;
+    use crate::cli::wrap_string::*;
 
     #[test]
     fn wrap_string() {
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Exit code used if everything went fine.
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// Exit code used if nothing could be done at all, e.g. the target couldn't be created.
+pub const EXIT_TOTAL_FAILURE: i32 = 1;
+
+/// Exit code used if the arguments passed are invalid.
+pub const EXIT_USAGE: i32 = 2;
+
+/// Exit code used if the source directory does not exist.
+pub const EXIT_SOURCE_MISSING: i32 = 3;
+
+/// Exit code used if some, but not all, files couldn't be processed.
+pub const EXIT_PARTIAL_FAILURE: i32 = 4;
+
+/// Errors which abort a run and determine the exit code of the process.
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid cli-arguments.
+    Usage(String),
+
+    /// The source directory does not exist.
+    SourceNotFound(PathBuf),
+
+    /// The target directory couldn't be created.
+    TargetUnavailable(PathBuf, io::Error),
+
+    /// The source directory couldn't be read.
+    ReadDir(PathBuf, io::Error),
+
+    /// An ignore-file couldn't be read.
+    IgnoreFile(PathBuf, io::Error),
+
+    /// The log-file couldn't be created or updated.
+    LogFile(PathBuf, io::Error),
+
+    /// Some of the files errored.
+    PartialFailure { errored: usize, total: usize },
+
+    /// Every file errored.
+    TotalFailure { errored: usize },
+}
+
+impl Error {
+    /// Returns the exit code corresponding to this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => EXIT_USAGE,
+            Error::SourceNotFound(_) => EXIT_SOURCE_MISSING,
+            Error::PartialFailure { .. } | Error::LogFile(..) => EXIT_PARTIAL_FAILURE,
+            Error::TargetUnavailable(..)
+            | Error::ReadDir(..)
+            | Error::IgnoreFile(..)
+            | Error::TotalFailure { .. } => EXIT_TOTAL_FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(msg) => write!(f, "{}", msg),
+            Error::SourceNotFound(path) => write!(f, "Source not found: {:?}", path),
+            Error::TargetUnavailable(path, e) => {
+                write!(f, "Failed to create target {:?} ({})", path, e)
+            }
+            Error::ReadDir(path, e) => write!(f, "Failed to read directory {:?} ({})", path, e),
+            Error::IgnoreFile(path, e) => {
+                write!(f, "Failed to read ignore-file {:?} ({})", path, e)
+            }
+            Error::LogFile(path, e) => write!(f, "Failed to update log-file {:?} ({})", path, e),
+            Error::PartialFailure { errored, total } => {
+                write!(f, "{} of {} files could not be processed.", errored, total)
+            }
+            Error::TotalFailure { errored } => {
+                write!(f, "None of the {} files could be processed.", errored)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Kinds of errors which may occur while moving a single file.
+/// More kinds may be added, matches need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileErrorKind {
+    /// The destination directory couldn't be created.
+    CreateDirFailed,
+
+    /// The file couldn't be moved.
+    RenameFailed,
+
+    /// The file would have to be moved across devices.
+    CrossDevice,
+
+    /// Missing permissions to move the file or to create the destination directory.
+    PermissionDenied,
+
+    /// A file with the same name already exists at the destination.
+    DestinationExists,
+
+    /// The file couldn't be removed.
+    RemoveFailed,
+
+    /// The file couldn't be moved to the trash.
+    TrashFailed,
+}
+
+/// Error of a single file, keeps the paths involved and the underlying io-error if there is one.
+#[derive(Debug)]
+pub struct FileError {
+    /// What went wrong.
+    pub kind: FileErrorKind,
+
+    /// The file which should've been moved.
+    pub path: PathBuf,
+
+    /// Where the file should've been moved to, if already known.
+    pub destination: Option<PathBuf>,
+
+    /// The underlying io-error.
+    pub source: Option<io::Error>,
+}
+
+impl FileError {
+    /// Creates a new error without destination or io-error.
+    pub fn new(kind: FileErrorKind, path: &Path) -> Self {
+        FileError {
+            kind,
+            path: path.to_path_buf(),
+            destination: Option::None,
+            source: Option::None,
+        }
+    }
+
+    /// Creates a new error out of an io-error.
+    /// `fallback` is used if the io-error doesn't map to a more specific kind.
+    pub fn from_io(fallback: FileErrorKind, path: &Path, destination: &Path, e: io::Error) -> Self {
+        let kind = match e.kind() {
+            io::ErrorKind::PermissionDenied => FileErrorKind::PermissionDenied,
+            io::ErrorKind::CrossesDevices => FileErrorKind::CrossDevice,
+            io::ErrorKind::AlreadyExists => FileErrorKind::DestinationExists,
+            _ => fallback,
+        };
+
+        FileError {
+            kind,
+            path: path.to_path_buf(),
+            destination: Option::Some(destination.to_path_buf()),
+            source: Option::Some(e),
+        }
+    }
+
+    /// Sets the destination.
+    pub fn destination(mut self, destination: &Path) -> Self {
+        self.destination = Option::Some(destination.to_path_buf());
+        self
+    }
+
+    /// Returns the kind of the underlying io-error, if any.
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        self.source.as_ref().map(|e| e.kind())
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            FileErrorKind::CreateDirFailed => "cannot create directory",
+            FileErrorKind::RenameFailed => "rename failed",
+            FileErrorKind::CrossDevice => "crosses devices",
+            FileErrorKind::PermissionDenied => "permission denied",
+            FileErrorKind::DestinationExists => "destination already exists",
+            FileErrorKind::TrashFailed => "cannot move to trash",
+            FileErrorKind::RemoveFailed => {
+                return match &self.source {
+                    Some(e) => write!(f, "Failed to remove {:?} ({})", self.path, e),
+                    None => write!(f, "Failed to remove {:?}", self.path),
+                }
+            }
+        };
+
+        write!(f, "Failed to move {:?}", self.path)?;
+
+        if let Some(destination) = &self.destination {
+            write!(f, " to {:?}", destination)?;
+        }
+
+        write!(f, ": {}", reason)?;
+
+        if let Some(e) = &self.source {
+            write!(f, " ({})", e)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
@@ -1,87 +1,212 @@
-use std::path::PathBuf;
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::bundle::{primary_extension, Bundle};
+use crate::busy::BusyReason;
+use crate::error::{FileError, FileErrorKind};
+use crate::ignore::IgnoreRule;
+use crate::rename::Rename;
+use crate::retention::ExpiryReason;
+use crate::route::{routed, Route};
+
+/// Options deciding which files get moved.
+#[derive(Debug, Clone)]
 pub struct Options {
+    /// Extensions which are never moved.
     pub excluded: Option<Vec<String>>,
+
+    /// If set, only files with one of these extensions are moved.
     pub included: Option<Vec<String>>,
+
+    /// Only checks which files would be moved, without moving them.
     pub dry_run: bool,
+
+    /// Leaves unfinished downloads and files opened by other processes untouched.
+    pub busy_check: bool,
+
+    /// Leaves files untouched which have been modified less than this ago.
+    pub min_age: Option<Duration>,
+
+    /// Transforms applied to the name of each moved file.
+    pub rename: Rename,
+
+    /// Companion files are moved to the same directory as their primary file.
+    pub bundles: Vec<Bundle>,
+
+    /// Files matching a route are moved into its directory instead of the destination.
+    pub routes: Vec<Route>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            excluded: Option::None,
+            included: Option::None,
+            dry_run: false,
+            busy_check: true,
+            min_age: Option::None,
+            rename: Rename::default(),
+            bundles: Bundle::builtin(),
+            routes: Vec::new(),
+        }
+    }
 }
 
+/// Outcome of [`accept`] for a single file.
+/// More outcomes may be added, matches need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum FileResult {
+    /// The file has been moved to the contained path.
     Moved(PathBuf),
+
+    /// The file has been filtered out by its extension or doesn't have one.
     Skipped,
+
+    /// The file matched a rule of an ignore-file.
+    Ignored(IgnoreRule),
+
+    /// The file is still in use, e.g. it's being downloaded.
+    Busy(BusyReason),
+
+    /// The file would have been moved but a dry-run is performed.
     Checked,
-    Errored(String),
-}
 
-fn contains_extension(list: &Option<Vec<String>>, ext: &String) -> bool {
-    if list.is_none() {
-        return true;
-    }
+    /// The file would have been moved to the contained path but a dry-run is performed.
+    Planned(PathBuf),
+
+    /// The file has been removed by a retention-policy.
+    Deleted(ExpiryReason),
+
+    /// The file has been moved to the contained path in the trash by a retention-policy.
+    Trashed(PathBuf, ExpiryReason),
 
-    list.as_ref().unwrap().contains(ext)
+    /// The file would have been removed by a retention-policy but a dry-run is performed.
+    Expired(ExpiryReason),
+
+    /// The file couldn't be moved.
+    Errored(FileError),
 }
 
 /**
- * Moves a file to the corresponding destination directory
+ * Moves a file to the corresponding destination directory, e.g. `<destination>/<extension>/<file>`.
+ * Companion files end up in the directory of their primary file, e.g. `<destination>/jpg/<file>.xmp`.
+ * Files matching a route are moved directly into its directory, e.g. `~/Pictures/<file>`.
  */
-pub fn accept(path: &PathBuf, destination: &PathBuf, options: &Options) -> FileResult {
+pub fn accept(path: &Path, destination: &Path, options: &Options) -> FileResult {
     let extension = match path.extension() {
         Some(os_str) => os_str,
-        None => {
-            return FileResult::Errored(format!(
-                "Failed to resolve extension of \"{}\"",
-                path.to_str().unwrap()
-            ));
-        }
+        None => return FileResult::Skipped,
     };
 
-    // Filter
-    let extension_string = match &extension.to_str() {
-        None => return FileResult::Errored(String::from("Cannot decode extension.")),
-        Some(s) => s.to_string(),
-    };
+    // Filter, extensions which aren't valid UTF-8 never match
+    let listed = |list: &Vec<String>| list.iter().any(|e| OsStr::new(e) == extension);
 
-    match &options.included {
-        None => (),
-        Some(list) => {
-            if !list.contains(&extension_string) {
-                return FileResult::Skipped;
-            }
+    if let Some(list) = &options.included {
+        if !listed(list) {
+            return FileResult::Skipped;
         }
     }
 
-    match &options.excluded {
-        None => (),
-        Some(list) => {
-            if list.contains(&extension_string) {
-                return FileResult::Skipped;
-            }
+    if let Some(list) = &options.excluded {
+        if listed(list) {
+            return FileResult::Skipped;
         }
     }
 
+    let category = primary_extension(path, destination, options);
+    let category = category.as_deref().unwrap_or(extension);
+    let destination_directory = match category.to_str().and_then(|c| routed(&options.routes, c)) {
+        Some(directory) => directory.to_path_buf(),
+        None => destination.join(category),
+    };
+
     // Check if dry-run should be performed
+    let name = options.rename.file_name(path);
     if options.dry_run {
-        return FileResult::Checked;
+        return FileResult::Planned(destination_directory.join(name));
     }
 
-    let destination_directory = destination.join(extension);
     if !destination_directory.exists() {
-        match std::fs::create_dir(&destination_directory) {
-            Ok(_) => (),
-            Err(e) => {
-                return FileResult::Errored(format!(
-                    "Failed to create directory: {:?} ({})",
-                    destination_directory,
-                    e.to_string()
-                ));
-            }
+        if let Err(e) = std::fs::create_dir_all(&destination_directory) {
+            return FileResult::Errored(FileError::from_io(
+                FileErrorKind::CreateDirFailed,
+                path,
+                &destination_directory,
+                e,
+            ));
         }
     }
 
-    let target = PathBuf::from(&destination_directory).join(path.file_name().unwrap());
+    move_file_to(path, &destination_directory.join(name))
+}
+
+/**
+ * Copies a file to another file-system and removes the original, never overwrites existing files.
+ * The copy is synced to disk before the original is removed, it's removed again on failure.
+ */
+fn copy_file_to(path: &Path, target: &Path) -> io::Result<()> {
+    let mut source = File::open(path)?;
+    let metadata = source.metadata()?;
+    let mut copy = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+
+    let copied = io::copy(&mut source, &mut copy)
+        .and_then(|_| copy.set_permissions(metadata.permissions()))
+        .and_then(|_| match metadata.modified() {
+            Ok(modified) => copy.set_modified(modified),
+            Err(_) => Ok(()),
+        })
+        .and_then(|_| copy.sync_all())
+        .and_then(|_| std::fs::remove_file(path));
+
+    copied.inspect_err(|_| {
+        std::fs::remove_file(target).ok();
+    })
+}
+
+/**
+ * Moves a file into a directory, never overwrites existing files.
+ */
+pub fn move_file(path: &Path, directory: &Path) -> FileResult {
+    move_file_to(path, &directory.join(path.file_name().unwrap()))
+}
+
+/**
+ * Moves a file to another path, never overwrites existing files.
+ * The file is hard-linked first as that fails if the target exists, unlike a rename which would
+ * replace it. Checking for the target beforehand would race with other processes.
+ * Files are copied to other file-systems, e.g. if a route points to another disk.
+ */
+pub fn move_file_to(path: &Path, target: &Path) -> FileResult {
+    let moved = match std::fs::hard_link(path, target) {
+        Ok(_) => std::fs::remove_file(path).inspect_err(|_| {
+            std::fs::remove_file(target).ok();
+        }),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => copy_file_to(path, target),
+
+        // Not all file-systems support hard-links, e.g. FAT
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists && !target.exists() => {
+            std::fs::rename(path, target)
+        }
+        Err(e) => Err(e),
+    };
 
-    match std::fs::rename(&path, &target) {
-        Ok(_) => FileResult::Moved(target),
-        Err(_) => FileResult::Errored(String::from("Failed to move file.")),
+    match moved {
+        Ok(_) => FileResult::Moved(target.to_path_buf()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => FileResult::Errored(
+            FileError::new(FileErrorKind::DestinationExists, path).destination(target),
+        ),
+        Err(e) => FileResult::Errored(FileError::from_io(
+            FileErrorKind::RenameFailed,
+            path,
+            target,
+            e,
+        )),
     }
 }
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the files containing ignore-patterns.
+pub const IGNORE_FILE: &str = ".cleanupignore";
+
+/// A single line of an ignore-file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreRule {
+    /// The ignore-file this rule is defined in.
+    pub file: PathBuf,
+
+    /// Line number, starting at 1.
+    pub line: usize,
+
+    /// The line as written in the file.
+    pub source: String,
+
+    /// Whenever this rule re-includes previously ignored paths, e.g. `!important.pdf`.
+    pub negated: bool,
+
+    /// Whenever this rule only matches directories, e.g. `build/`.
+    pub dir_only: bool,
+
+    /// Glob matched against the path relative to the directory of the ignore-file.
+    pattern: Vec<char>,
+}
+
+/// Patterns of an ignore-file using the gitignore-syntax.
+/// Supports globs (`*`, `?`, `[a-z]`, `**`), negation via `!` and directory-only patterns.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    rules: Vec<IgnoreRule>,
+}
+
+/// Matches `text` against a glob-pattern, `*` and `?` never match a `/`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+
+            // "**/" also matches zero directories
+            if rest.first() == Some(&'/') && glob_match(&rest[1..], text) {
+                return true;
+            }
+
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some('?') => !text.is_empty() && text[0] != '/' && glob_match(&pattern[1..], &text[1..]),
+        Some('[') => match match_class(&pattern[1..], text.first()) {
+            Some((true, len)) => glob_match(&pattern[len + 1..], &text[1..]),
+            Some((false, _)) => false,
+            // Unclosed brackets are taken literally
+            None => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches a character against a class like `[a-z]` or `[!abc]` (without the opening bracket).
+/// Returns whenever it matched and the length of the class or None if the class isn't closed.
+fn match_class(class: &[char], c: Option<&char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!') | Some('^'));
+    let start = if negated { 1 } else { 0 };
+
+    // A "]" directly after the opening bracket is part of the class
+    let end = start + 1 + class.get(start + 1..)?.iter().position(|&c| c == ']')?;
+    let members = &class[start..end];
+
+    let c = match c {
+        None | Some('/') => return Some((false, end + 1)),
+        Some(c) => *c,
+    };
+
+    let mut matched = false;
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            matched |= members[i] <= c && c <= members[i + 2];
+            i += 3;
+        } else {
+            matched |= members[i] == c;
+            i += 1;
+        }
+    }
+
+    Some((matched != negated, end + 1))
+}
+
+impl IgnoreRule {
+    /// Parses a single line, returns None for empty lines and comments.
+    fn parse(file: &Path, line: usize, source: &str) -> Option<Self> {
+        let mut pattern = source.trim_end();
+
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return Option::None;
+        }
+
+        // A leading backslash escapes "!" and "#"
+        let negated = pattern.starts_with('!');
+        if negated || pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..];
+        }
+
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+
+        if pattern.is_empty() {
+            return Option::None;
+        }
+
+        // Patterns without a slash match at any level
+        let pattern = match pattern.contains('/') {
+            true => pattern.trim_start_matches('/').to_string(),
+            false => format!("**/{}", pattern),
+        };
+
+        Option::Some(IgnoreRule {
+            file: file.to_path_buf(),
+            line,
+            source: source.trim_end().to_string(),
+            negated,
+            dir_only,
+            pattern: pattern.chars().collect(),
+        })
+    }
+
+    /// Checks whenever a path, relative to the directory of the ignore-file, matches this rule.
+    pub fn matches(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        let text: Vec<char> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+            .chars()
+            .collect();
+
+        glob_match(&self.pattern, &text)
+    }
+}
+
+impl fmt::Display for IgnoreRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file.display(), self.line, self.source)
+    }
+}
+
+impl IgnoreFile {
+    /// Parses the content of an ignore-file located at `file`.
+    pub fn parse(file: &Path, content: &str) -> Self {
+        IgnoreFile {
+            rules: content
+                .lines()
+                .enumerate()
+                .filter_map(|(index, line)| IgnoreRule::parse(file, index + 1, line))
+                .collect(),
+        }
+    }
+
+    /// Loads the ignore-file of a directory, an empty one is returned if there is none.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let file = dir.join(IGNORE_FILE);
+
+        match std::fs::read_to_string(&file) {
+            Ok(content) => Ok(IgnoreFile::parse(&file, &content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(IgnoreFile::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the rules of this file.
+    pub fn rules(&self) -> &[IgnoreRule] {
+        &self.rules
+    }
+
+    /// Returns the last rule matching a path, negated ones included.
+    fn last_match(&self, relative: &Path, is_dir: bool) -> Option<&IgnoreRule> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(relative, is_dir))
+    }
+
+    /// Checks whenever a path, relative to the directory of this file, is ignored.
+    /// Returns the rule responsible for it, paths inside of ignored directories are ignored as well.
+    pub fn matched(&self, relative: &Path, is_dir: bool) -> Option<&IgnoreRule> {
+        for ancestor in relative.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+
+            if let Some(rule) = self.last_match(ancestor, true) {
+                if !rule.negated {
+                    return Option::Some(rule);
+                }
+            }
+        }
+
+        self.last_match(relative, is_dir)
+            .filter(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ignore::*;
+
+    fn ignored(content: &str, path: &str, is_dir: bool) -> Option<usize> {
+        IgnoreFile::parse(Path::new(IGNORE_FILE), content)
+            .matched(Path::new(path), is_dir)
+            .map(|rule| rule.line)
+    }
+
+    #[test]
+    fn globs() {
+        let content = "*.iso\nfoo?.txt\n[ab]*.pdf\n**/deep/*.mp4";
+        assert_eq!(ignored(content, "image.iso", false), Some(1));
+        assert_eq!(ignored(content, "sub/image.iso", false), Some(1));
+        assert_eq!(ignored(content, "foo1.txt", false), Some(2));
+        assert_eq!(ignored(content, "foo12.txt", false), None);
+        assert_eq!(ignored(content, "a.pdf", false), Some(3));
+        assert_eq!(ignored(content, "c.pdf", false), None);
+        assert_eq!(ignored(content, "deep/m.mp4", false), Some(4));
+        assert_eq!(ignored(content, "x/deep/m.mp4", false), Some(4));
+        assert_eq!(ignored(content, "m.mp4", false), None);
+    }
+
+    #[test]
+    fn negation() {
+        let content = "# Comment\n*.pdf\n!important.pdf\n\\#notes.txt";
+        assert_eq!(ignored(content, "a.pdf", false), Some(2));
+        assert_eq!(ignored(content, "important.pdf", false), None);
+        assert_eq!(ignored(content, "#notes.txt", false), Some(4));
+    }
+
+    #[test]
+    fn directories() {
+        let content = "build/\n/anchored.txt";
+        assert_eq!(ignored(content, "build", true), Some(1));
+        assert_eq!(ignored(content, "build", false), None);
+        assert_eq!(ignored(content, "build/a.txt", false), Some(1));
+        assert_eq!(ignored(content, "anchored.txt", false), Some(2));
+        assert_eq!(ignored(content, "sub/anchored.txt", false), None);
+    }
+
+    #[test]
+    fn classes() {
+        let content = "[!a-c].txt\n[].txt";
+        assert_eq!(ignored(content, "d.txt", false), Some(1));
+        assert_eq!(ignored(content, "b.txt", false), None);
+        assert_eq!(ignored(content, "[].txt", false), Some(2));
+    }
+}
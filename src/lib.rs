@@ -0,0 +1,47 @@
+//! Sorts files with the same kind of extension into directories.
+//!
+//! ```no_run
+//! use cleanup_files::{Event, FileResult, Options, Sorter};
+//!
+//! let summary = Sorter::new("./downloads")
+//!     .target("./downloads/.archive")
+//!     .options(Options {
+//!         excluded: Some(vec![String::from("part")]),
+//!         ..Options::default()
+//!     })
+//!     .run(|event| {
+//!         if let Event::Processed { path, result: FileResult::Moved(dest), .. } = event {
+//!             println!("{:?} -> {:?}", path, dest);
+//!         }
+//!     })
+//!     .unwrap();
+//!
+//! println!("{} files processed", summary.processed);
+//! ```
+
+pub use bundle::Bundle;
+pub use busy::BusyReason;
+pub use error::{Error, FileError, FileErrorKind};
+pub use file::{accept, move_file, move_file_to, FileResult, Options};
+pub use ignore::{IgnoreFile, IgnoreRule};
+pub use rename::Rename;
+pub use restore::Restorer;
+pub use retention::{ExpiryReason, Retention};
+pub use route::Route;
+pub use sorter::{Decision, Event, Sorter, Summary};
+pub use trash::Trash;
+
+pub mod bundle;
+pub mod busy;
+pub mod error;
+pub mod file;
+pub mod ignore;
+pub mod log;
+pub mod rename;
+pub mod restore;
+pub mod retention;
+pub mod route;
+pub mod sorter;
+pub mod trash;
+mod utils;
+pub mod xdg;
@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::file::FileResult;
+
+/// Format of the timestamp each line of a log-file starts with.
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Separators between the paths of a line, spaces starting one of them are escaped in paths.
+const DELIMITERS: [&str; 2] = [" ->", " (source: "];
+
+/// A file which has been moved according to a log-file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMove {
+    /// When the file has been moved.
+    pub time: DateTime<Utc>,
+
+    /// Where the file has been moved from.
+    pub from: PathBuf,
+
+    /// Where the file has been moved to.
+    pub to: PathBuf,
+}
+
+/// Returns the raw bytes of a path.
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec()
+}
+
+/// Paths are always valid unicode on this platform, apart from unpaired surrogates.
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+/// Converts raw bytes back into a path.
+#[cfg(unix)]
+fn bytes_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes))
+}
+
+/// Converts raw bytes back into a path.
+#[cfg(not(unix))]
+fn bytes_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/**
+ * Escapes a path losslessly so it can be written to a log-file.
+ * Bytes which aren't valid UTF-8 and control characters are written as `\xNN`, backslashes only
+ * need to be escaped as `\\` if they'd be ambiguous otherwise, e.g. `C:\Users` is kept as it is.
+ * Spaces are written as `\x20` if they'd start a separator, e.g. in `My -> File.txt`.
+ */
+pub fn escape_path(path: &Path) -> String {
+    let bytes = path_bytes(path);
+    let mut rest = bytes.as_slice();
+
+    // Whenever a backslash is ambiguous depends on what's written after it, even if that's escaped
+    let mut tokens: Vec<String> = Vec::new();
+
+    while !rest.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(rest) {
+            Ok(valid) => (valid, 0),
+            Err(e) => {
+                let (valid, _) = rest.split_at(e.valid_up_to());
+                let invalid = e.error_len().unwrap_or(rest.len() - valid.len());
+                (std::str::from_utf8(valid).unwrap(), invalid)
+            }
+        };
+
+        for c in valid.chars() {
+            match c {
+                c if c.is_control() && c.is_ascii() => tokens.push(format!("\\x{:02X}", c as u8)),
+                c => tokens.push(c.to_string()),
+            }
+        }
+
+        let start = valid.len();
+        for byte in &rest[start..start + invalid] {
+            tokens.push(format!("\\x{:02X}", byte));
+        }
+
+        rest = &rest[start + invalid..];
+    }
+
+    // Escaped bytes are a token of their own, so they never match a separator
+    let starts_delimiter = |index: usize| {
+        DELIMITERS.iter().any(|delimiter| {
+            let length = delimiter.chars().count();
+            tokens[index..]
+                .iter()
+                .take(length)
+                .map(String::as_str)
+                .collect::<String>()
+                == *delimiter
+        })
+    };
+
+    let mut escaped = String::with_capacity(bytes.len());
+    for (index, token) in tokens.iter().enumerate() {
+        let next = tokens.get(index + 1).and_then(|next| next.chars().next());
+        match token.as_str() {
+            "\\" if matches!(next, Some('x') | Some('\\')) => escaped.push_str("\\\\"),
+            " " if starts_delimiter(index) => escaped.push_str("\\x20"),
+            token => escaped.push_str(token),
+        }
+    }
+
+    escaped
+}
+
+/**
+ * Reverts [`escape_path`], unknown escape sequences are kept as they are.
+ */
+pub fn unescape_path(escaped: &str) -> PathBuf {
+    let bytes = escaped.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = escaped
+            .get(i + 2..i + 4)
+            .filter(|_| bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x'))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], bytes.get(i + 1), hex) {
+            (_, _, Some(byte)) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            (b'\\', Some(b'\\'), _) => {
+                unescaped.push(b'\\');
+                i += 2;
+            }
+            (byte, _, _) => {
+                unescaped.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    bytes_path(unescaped)
+}
+
+/**
+ * Formats a single line of a log-file, without the trailing line-break.
+ */
+pub fn format_line(
+    time: &DateTime<Utc>,
+    source: &Path,
+    path: &Path,
+    result: &FileResult,
+) -> String {
+    let path = escape_path(path);
+    let entry = match result {
+        FileResult::Errored(error) => format!("[ERRORED] {} ({})", path, error),
+        FileResult::Moved(dest) => format!("[MOVED] {} -> {}", path, escape_path(dest)),
+        FileResult::Skipped => format!("[SKIPPED] {}", path),
+        FileResult::Ignored(rule) => format!("[SKIPPED] {} (ignored by {})", path, rule),
+        FileResult::Busy(reason) => format!("[BUSY] {} ({})", path, reason),
+        FileResult::Checked => format!("[CHECKED] {}", path),
+        FileResult::Planned(dest) => format!("[CHECKED] {} -> {}", path, escape_path(dest)),
+        FileResult::Deleted(reason) => format!("[DELETED] {} ({})", path, reason),
+        FileResult::Trashed(dest, reason) => {
+            format!("[TRASHED] {} -> {} ({})", path, escape_path(dest), reason)
+        }
+        FileResult::Expired(reason) => format!("[EXPIRED] {} ({})", path, reason),
+    };
+
+    format!(
+        "{} {} (source: {})",
+        time.format(TIME_FORMAT),
+        entry,
+        escape_path(source)
+    )
+}
+
+/// Parses a single "[MOVED]" line.
+fn parse_move(line: &str) -> Option<LogMove> {
+    let time = NaiveDateTime::parse_from_str(line.get(..19)?, TIME_FORMAT).ok()?;
+    let entry = line.get(19..)?.strip_prefix(" [MOVED] ")?;
+
+    // Older log-files don't contain the source
+    let entry = match entry.rfind(" (source: ") {
+        Some(index) => &entry[..index],
+        None => entry,
+    };
+
+    let (from, to) = entry.split_once(" -> ")?;
+    Option::Some(LogMove {
+        time: DateTime::from_utc(time, Utc),
+        from: unescape_path(from),
+        to: unescape_path(to),
+    })
+}
+
+/**
+ * Parses all moves of a log-file, other lines are skipped.
+ */
+pub fn parse_moves(content: &str) -> Vec<LogMove> {
+    content.lines().filter_map(parse_move).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::log::*;
+
+    #[test]
+    fn round_trip() {
+        let time = DateTime::from_utc(
+            NaiveDateTime::parse_from_str("2020-02-03 10:20:30", TIME_FORMAT).unwrap(),
+            Utc,
+        );
+
+        let line = format_line(
+            &time,
+            Path::new("/home"),
+            Path::new("/home/a.txt"),
+            &FileResult::Moved(PathBuf::from("/home/.archive/txt/a.txt")),
+        );
+
+        assert_eq!(
+            line,
+            "2020-02-03 10:20:30 [MOVED] /home/a.txt -> /home/.archive/txt/a.txt (source: /home)"
+        );
+
+        let content = format!(
+            "{}\n2020-02-03 10:20:31 [SKIPPED] /home/b\n2020-02-03 10:20:32 [MOVED] /a.mp4 -> /b/a.mp4",
+            line
+        );
+
+        let moves = parse_moves(&content);
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].time, time);
+        assert_eq!(moves[0].from, PathBuf::from("/home/a.txt"));
+        assert_eq!(moves[0].to, PathBuf::from("/home/.archive/txt/a.txt"));
+        assert_eq!(moves[1].to, PathBuf::from("/b/a.mp4"));
+    }
+
+    #[test]
+    fn separators() {
+        let time = DateTime::from_utc(
+            NaiveDateTime::parse_from_str("2020-02-03 10:20:30", TIME_FORMAT).unwrap(),
+            Utc,
+        );
+
+        for name in [
+            "My -> File.TXT",
+            "a ->",
+            "b -> -> c",
+            "d (source: e)",
+            "f ->g",
+        ] {
+            let source = PathBuf::from(format!("/{}", name));
+            let path = source.join(name);
+            let dest = source.join(".archive").join(name);
+            let line = format_line(&time, &source, &path, &FileResult::Moved(dest.clone()));
+
+            let moves = parse_moves(&line);
+            assert_eq!(moves.len(), 1, "{}", line);
+            assert_eq!(moves[0].from, path);
+            assert_eq!(moves[0].to, dest);
+        }
+
+        assert_eq!(escape_path(Path::new("My -> File")), "My\\x20-> File");
+        assert_eq!(escape_path(Path::new("a - b")), "a - b");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn escaping() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(
+            b"/a\\x41\\\\/b\xff\ne\xc3\xa4\\",
+        ));
+        let escaped = escape_path(path);
+        assert_eq!(escaped, "/a\\\\x41\\\\\\/b\\xFF\\x0Aeä\\");
+        assert_eq!(unescape_path(&escaped), path);
+        assert_eq!(unescape_path("/a\\q\\x4"), Path::new("/a\\q\\x4"));
+
+        // Backslashes followed by an escaped byte
+        for bytes in [&b"a\\\n"[..], b"a\\\xff", b"a\\\\\xff", b"\\"] {
+            let path = Path::new(std::ffi::OsStr::from_bytes(bytes));
+            assert_eq!(unescape_path(&escape_path(path)), path);
+        }
+
+        assert_eq!(
+            escape_path(Path::new(std::ffi::OsStr::from_bytes(b"a\\\n"))),
+            "a\\\\\\x0A"
+        );
+    }
+}
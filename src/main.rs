@@ -1,37 +1,305 @@
 #![allow(dead_code)]
 
-use crate::cli::flag::CLIFlag;
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use cleanup_files::error::{
+    EXIT_PARTIAL_FAILURE, EXIT_SOURCE_MISSING, EXIT_SUCCESS, EXIT_TOTAL_FAILURE, EXIT_USAGE,
+};
+use cleanup_files::{Bundle, Options, Rename, Restorer, Retention, Route, Sorter, Trash};
+
+use crate::cli::completion::{Completion, Shell};
+use crate::cli::flag::{CLIFlag, Multiplicity};
+use crate::cli::result::CLIResult;
+use crate::cli::types::{ByteSize, TimeSpan, ValueType};
 use crate::cli::value::CLIValue;
 use crate::cli::CLIApp;
 
 mod cli;
-mod file;
-mod lib;
+mod prompt;
 mod run;
 
+/// Splits comma-separated lists, each of them may be passed multiple times.
+/// Commas which are part of a value are escaped as `\,`, e.g. `-i 'b\,c'`.
+fn resolve_cs_list(vals: &[String]) -> Option<Vec<String>> {
+    if vals.is_empty() {
+        return Option::None;
+    }
+
+    let mut list = Vec::new();
+    for val in vals {
+        let mut item = String::new();
+        let mut chars = val.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&',') => item.push(chars.next().unwrap()),
+                ',' => list.push(std::mem::take(&mut item)),
+                c => item.push(c),
+            }
+        }
+
+        list.push(item);
+    }
+
+    Option::Some(list)
+}
+
+/// Extensions suggested by shell-completions.
+fn known_extensions() -> Completion {
+    let mut extensions: Vec<String> = cleanup_files::xdg::USER_DIRS
+        .iter()
+        .flat_map(|(_, _, extensions)| extensions.iter().map(|s| s.to_string()))
+        .collect();
+
+    extensions.sort();
+    extensions.dedup();
+    Completion::Words(extensions)
+}
+
+/// Parses bundles separated by a semicolon, e.g. `obj:mtl;blend:blend1`.
+fn parse_bundles(val: &str) -> Result<Vec<Bundle>, String> {
+    val.split(';')
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.parse())
+        .collect()
+}
+
+/// Parses routes separated by a semicolon, e.g. `iso,img=/mnt/isos;pdf=/srv/docs`.
+fn parse_routes(val: &str) -> Result<Vec<Route>, String> {
+    val.split(';')
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.parse())
+        .collect()
+}
+
+/// Resolves the source directories and the target directory, if passed.
+fn resolve_paths(app: &CLIResult) -> (Vec<&String>, Option<&String>) {
+    let mut sources: Vec<&String> = app.get_values("source").iter().collect();
+    let mut target = app.get_value("target");
+
+    // All values are sources if the target is passed explicitly
+    if let Some(explicit_target) = app.get_arg("target") {
+        sources.extend(target);
+        target = Option::Some(explicit_target);
+    }
+
+    (sources, target)
+}
+
+/// Resolves the archive, e.g. the target directory.
+fn resolve_archive(app: &CLIResult) -> PathBuf {
+    let (sources, target) = resolve_paths(app);
+    match target {
+        Some(target) => PathBuf::from(target),
+        None => PathBuf::from(sources[0]).join(".archive"),
+    }
+}
+
+/// Creates a restorer based on the parsed cli-arguments.
+/// Files are moved from the target back into the first source.
+/// The log-file is used to restore the original names of renamed files.
+fn create_restorer(app: &CLIResult, log_file: Option<&str>) -> Restorer {
+    let (sources, _) = resolve_paths(app);
+    let archive = resolve_archive(app);
+    let restorer = Restorer::new(&archive, sources[0]).dry_run(app.has_flag("dry"));
+
+    match log_file {
+        Some(log_file) => restorer.log_file(archive.join(log_file)),
+        None => restorer,
+    }
+}
+
+/// Creates a retention-policy for the target based on the parsed cli-arguments.
+/// The log-file is used to resolve when files got archived.
+fn create_retention(app: &CLIResult, log_file: Option<&str>) -> Retention {
+    let archive = resolve_archive(app);
+    let trash = match app.has_flag("permanent") {
+        true => Option::None,
+        false => Option::Some(Trash::new()),
+    };
+
+    let mut retention = Retention::new(&archive)
+        .dry_run(app.has_flag("dry"))
+        .trash(trash);
+
+    if let Some(log_file) = log_file {
+        retention = retention.log_file(archive.join(log_file));
+    }
+
+    if let Some(TimeSpan(age)) = app.get("expire-after") {
+        retention = retention.max_age(age);
+    }
+
+    if let Some(count) = app.get("keep-newest") {
+        retention = retention.keep_newest(count);
+    }
+
+    if let Some(ByteSize(bytes)) = app.get("max-size") {
+        retention = retention.max_size(bytes);
+    }
+
+    retention
+}
+
+/// Creates a sorter based on the parsed cli-arguments.
+fn create_sorter(app: &CLIResult) -> Sorter {
+    let (sources, target) = resolve_paths(app);
+    let mut bundles = Bundle::builtin();
+    for custom in app.get_args("bundles") {
+        bundles.extend(parse_bundles(custom).unwrap());
+    }
+
+    // Explicit routes take precedence over the XDG user directories
+    let mut routes: Vec<Route> = app
+        .get_args("routes")
+        .iter()
+        .flat_map(|routes| parse_routes(routes).unwrap())
+        .collect();
+
+    if app.has_flag("xdg") {
+        routes.extend(cleanup_files::xdg::routes());
+    }
+
+    let mut sorter = Sorter::new(sources[0]).options(Options {
+        dry_run: app.has_flag("dry"),
+        excluded: resolve_cs_list(app.get_args("excluded")),
+        included: resolve_cs_list(app.get_args("included")),
+        min_age: app.get("min-age").map(|TimeSpan(age)| age),
+        busy_check: app.has_flag("busy-check"),
+        rename: app.get_args("rename").join(",").parse().unwrap(),
+        bundles,
+        routes,
+    });
+
+    for source in &sources[1..] {
+        sorter = sorter.source(*source);
+    }
+
+    if let Some(target) = target {
+        sorter = sorter.target(target);
+    }
+
+    // Resolve current executable to prevent sorting it
+    if let Ok(current_exe) = std::env::current_exe() {
+        sorter = sorter.ignore(current_exe);
+    }
+
+    sorter
+}
+
 fn main() {
-    let cli_app = CLIApp::new()
+    let mut cli_app = CLIApp::new()
         .name("cleanup")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(env!("CARGO_PKG_DESCRIPTION"))
+        .long_description("Moves all files of the source directories into <target>/<extension>/<file>, the target defaults to the first source + .archive. Hidden files, the executable itself and files matching a .cleanupignore are left untouched.\n\nEverything that happened is appended to a log-file in the target, which is used to restore the original names of renamed files.")
+        .example("cleanup", "Moves all files of the current directory into ./.archive/<extension>/.")
+        .example("cleanup ~/Downloads ~/Desktop --target ~/archive", "Moves the files of both directories into ~/archive.")
+        .example("cleanup . --restore", "Moves all files from ./.archive back into the current directory.")
+        .example("cleanup --expire-after 30d --dry-run", "Lists all files which have been archived more than 30 days ago.")
+        .exit_code(EXIT_SUCCESS, "Everything went fine.")
+        .exit_code(EXIT_TOTAL_FAILURE, "Total failure, e.g. the target couldn't be created or none of the files could be moved.")
+        .exit_code(EXIT_USAGE, "Invalid arguments.")
+        .exit_code(EXIT_SOURCE_MISSING, "The source directory does not exist.")
+        .exit_code(EXIT_PARTIAL_FAILURE, "Partial failure, some of the files couldn't be moved or the log-file couldn't be written.")
         .add_flag(
             CLIFlag::new("log")
-                .description("Creates (or disables) a log-file in the target folder. Default is 'cleanup.log'.")
+                .description("Appends what happened to a log-file in the target folder.")
+                .enabled_by_default(true)
+                .env("CLEANUP_LOG")
+                .abbr("--log")
+        )
+        .add_flag(
+            CLIFlag::new("log-file")
+                .description("Name of the log-file in the target folder. Default is 'cleanup.log'.")
                 .default(|_| String::from("cleanup.log"))
-                .value_description("file|boolean")
+                .value_description("file")
+                .complete(Completion::Files)
+                .env("CLEANUP_LOG_FILE")
+                .requires("log")
                 .abbr("-l")
                 .abbr("--log-file")
         )
         .add_flag(
             CLIFlag::new("dry")
                 .description("Performs a dry-run, e.g. nothing get's moved.")
+                .env("CLEANUP_DRY_RUN")
                 .abbr("-d")
                 .abbr("--dry")
                 .abbr("--dry-run")
         )
+        .add_flag(
+            CLIFlag::new("target")
+                .description("Target directory, all values are used as sources if set.")
+                .expects_value(true)
+                .value_description("directory")
+                .value_type(ValueType::Path)
+                .complete(Completion::Directories)
+                .env("CLEANUP_TARGET")
+                .abbr("-t")
+                .abbr("--target")
+        )
+        .add_flag(
+            CLIFlag::new("restore")
+                .description("Moves all files from the target back into the source.")
+                .exclusive_group("mode")
+                .abbr("--restore")
+                .abbr("--flatten")
+        )
+        .add_flag(
+            CLIFlag::new("expire-after")
+                .description("Removes files from the target which have been archived more than this ago, e.g. 30d or 12h.")
+                .value_type(ValueType::Duration)
+                .conflicts_with("restore")
+                .conflicts_with("interactive")
+                .abbr("--expire-after")
+        )
+        .add_flag(
+            CLIFlag::new("keep-newest")
+                .description("Removes all but the newest files of each category from the target.")
+                .value_type(ValueType::Integer)
+                .value_description("count")
+                .conflicts_with("restore")
+                .conflicts_with("interactive")
+                .abbr("--keep-newest")
+        )
+        .add_flag(
+            CLIFlag::new("max-size")
+                .description("Removes the oldest files from the target until it's at most this large, e.g. 500M.")
+                .value_type(ValueType::Size)
+                .conflicts_with("restore")
+                .conflicts_with("interactive")
+                .abbr("--max-size")
+        )
+        .add_flag(
+            CLIFlag::new("permanent")
+                .description("Removes expired files permanently instead of moving them to the trash.")
+                .env("CLEANUP_PERMANENT")
+                .abbr("--permanent")
+        )
+        .add_flag(
+            CLIFlag::new("xdg")
+                .description("Moves pictures, music, videos and documents into the XDG user directories.")
+                .env("CLEANUP_XDG")
+                .abbr("--xdg")
+        )
+        .add_flag(
+            CLIFlag::new("interactive")
+                .description("Asks what to do before moving each file.")
+                .exclusive_group("mode")
+                .abbr("--interactive")
+        )
         .add_flag(
             CLIFlag::new("included")
                 .description("Move only files with one of the following extensions.")
                 .expects_value(true)
-                .value_description("extensions...")
+                .multiplicity(Multiplicity::Repeated)
+                .value_description("extensions")
+                .complete(known_extensions())
+                .env("CLEANUP_INCLUDE")
+                .conflicts_with("excluded")
                 .abbr("-i")
                 .abbr("--include")
         )
@@ -39,55 +307,199 @@ fn main() {
             CLIFlag::new("excluded")
                 .description("Exclude certain files by their extension.")
                 .expects_value(true)
-                .value_description("extensions...")
+                .multiplicity(Multiplicity::Repeated)
+                .value_description("extensions")
+                .complete(known_extensions())
+                .env("CLEANUP_EXCLUDE")
                 .abbr("-e")
                 .abbr("--exclude")
         )
+        .add_flag(
+            CLIFlag::new("rename")
+                .description("Transforms the names of moved files, e.g. 'lowercase,spaces=-'.")
+                .expects_value(true)
+                .multiplicity(Multiplicity::Repeated)
+                .value_description("transforms")
+                .validate(|v| v.parse::<Rename>().map(|_| ()))
+                .env("CLEANUP_RENAME")
+                .abbr("-r")
+                .abbr("--rename")
+        )
+        .add_flag(
+            CLIFlag::new("bundles")
+                .description("Moves companion files along with their primary file, e.g. 'obj,fbx:mtl;blend:blend1'.")
+                .expects_value(true)
+                .multiplicity(Multiplicity::Repeated)
+                .value_description("bundles")
+                .validate(|v| parse_bundles(v).map(|_| ()))
+                .env("CLEANUP_BUNDLE")
+                .abbr("-b")
+                .abbr("--bundle")
+        )
+        .add_flag(
+            CLIFlag::new("routes")
+                .description("Moves files with certain extensions into other directories, e.g. 'iso,img=/mnt/isos;pdf=/srv/docs'.")
+                .expects_value(true)
+                .multiplicity(Multiplicity::Repeated)
+                .value_description("routes")
+                .validate(|v| parse_routes(v).map(|_| ()))
+                .env("CLEANUP_ROUTE")
+                .abbr("--route")
+        )
+        .add_flag(
+            CLIFlag::new("min-age")
+                .description("Leave files untouched which have been modified less than this ago, e.g. 90s or 2h.")
+                .value_type(ValueType::Duration)
+                .env("CLEANUP_MIN_AGE")
+                .abbr("--min-age")
+        )
+        .add_flag(
+            CLIFlag::new("busy-check")
+                .description("Leave files untouched which are still being downloaded or opened by another process.")
+                .enabled_by_default(true)
+                .env("CLEANUP_BUSY_CHECK")
+                .abbr("--busy-check")
+        )
+        .add_flag(
+            CLIFlag::new("color")
+                .description("Colors the output.")
+                .enabled_by_default(true)
+                .env("CLEANUP_COLOR")
+                .abbr("--color")
+        )
+        .add_flag(
+            CLIFlag::new("print-config")
+                .description("Prints the effective value of each option and where it comes from.")
+                .abbr("--print-config")
+        )
         .add_flag(
             CLIFlag::new("help")
                 .description("Prints this help text.")
+                .global(true)
+                .informational(true)
                 .abbr("-h")
                 .abbr("--help")
         )
         .add_flag(
             CLIFlag::new("version")
                 .description("Prints the current version.")
+                .global(true)
+                .informational(true)
                 .abbr("-v")
                 .abbr("--version")
         )
         .add_value(
             CLIValue::new("source")
                 .default(|_| ".".to_string())
-                .description("Source directory. Default is the current directory.")
+                .variadic(true)
+                .complete(Completion::Directories)
+                .description("Source directories. Default is the current directory.")
         )
         .add_value(
             CLIValue::new("target")
-                .default(|list| {
-                    let mut source = list.get("source").unwrap().clone();
-                    source.push_str("/.archive");
-                    source
-                })
-                .description("Target directory (Default is source + .archive).")
+                .complete(Completion::Directories)
+                .description("Target directory (Default is the first source + .archive).")
+        )
+        .add_subcommand(
+            CLIApp::new()
+                .name("completions")
+                .about("Prints a completion-script for the given shell.")
+                .add_value(
+                    CLIValue::new("shell")
+                        .value_type(ValueType::one_of(&["bash", "zsh", "fish"]))
+                        .required(true)
+                        .description("One of bash, zsh or fish.")
+                )
+        )
+        .add_subcommand(
+            CLIApp::new()
+                .name("man")
+                .about("Prints a man-page.")
         );
 
+    // Options with an environment variable can be set in $XDG_CONFIG_HOME/cleanup/config as well
+    cli_app = cli_app
+        .config_location("$XDG_CONFIG_HOME/cleanup/config (default ~/.config/cleanup/config)");
+    if let Some(config_home) = cleanup_files::xdg::config_home() {
+        cli_app = cli_app.config_file(&config_home.join("cleanup").join("config"));
+    }
+
     // Parse arguments
     let app = match cli_app.consume_args() {
         Err(e) => {
-            println!("{}\n", e.as_str());
-            cli_app.print_help();
-            return;
+            eprintln!("{}\n", e.as_str());
+            eprintln!("{}\n", cli_app.usage_description());
+            eprintln!("For more information try --help.");
+            std::process::exit(EXIT_USAGE);
         }
         Ok(v) => v,
     };
 
-    // Check if version or help is requested
-    if app.has_flag("help") {
-        cli_app.print_help();
+    if !app.innermost().has_flag("color") {
+        colored::control::set_override(false);
+    }
+
+    // Check if version or help is requested, they may be passed after a subcommand
+    if app.innermost().has_flag("help") {
+        print!("{}", cli_app.help_for(&app));
         return;
-    } else if app.has_flag("version") {
+    } else if app.innermost().has_flag("version") {
         println!("v{}", env!("CARGO_PKG_VERSION"));
         return;
+    } else if app.has_flag("print-config") {
+        if let Some(e) = app.deferred_error() {
+            eprintln!("{} {}", "⚠ Warning:".yellow(), e);
+        }
+
+        print!("{}", cli_app.config_report(&app));
+        return;
     }
 
-    run::start(app);
+    // Errors of the environment or config-file only matter if something is going to be done
+    if let Some(e) = app.deferred_error() {
+        eprintln!("{}\n", e);
+        eprintln!("For more information try --help.");
+        std::process::exit(EXIT_USAGE);
+    }
+
+    // Print the man-page or completion-scripts, e.g. "cleanup completions bash"
+    match app.subcommand() {
+        Some(("man", _)) => {
+            print!("{}", cli_app.man_page());
+            return;
+        }
+        Some(("completions", sub)) => {
+            let shell = sub.get_value("shell").unwrap().parse::<Shell>().unwrap();
+            print!("{}", cli_app.completion_script(shell));
+            return;
+        }
+        _ => (),
+    }
+
+    let log_name = match app.has_flag("log") {
+        true => Option::Some(
+            app.get_arg("log-file")
+                .map_or("cleanup.log", |s| s.as_str()),
+        ),
+        false => Option::None,
+    };
+
+    // Don't create a log-file if a dry-run is being performed
+    let log_file = log_name.filter(|_| !app.has_flag("dry"));
+    let retention = ["expire-after", "keep-newest", "max-size"]
+        .iter()
+        .any(|name| app.get_arg(name).is_some());
+
+    let result = if app.has_flag("restore") {
+        run::restore(&create_restorer(&app, log_name), log_file)
+    } else if retention {
+        run::expire(&create_retention(&app, log_name), log_file)
+    } else {
+        run::start(&create_sorter(&app), log_file, app.has_flag("interactive"))
+    };
+
+    if let Err(e) = result {
+        eprintln!("{} {}", "✖".red(), e);
+        std::process::exit(e.exit_code());
+    }
 }
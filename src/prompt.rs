@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use path_absolutize::Absolutize;
+
+use cleanup_files::Decision;
+
+/// Asks the user what to do with each file and remembers answers given for a whole extension.
+pub struct Prompt {
+    remembered: HashMap<OsString, Decision>,
+}
+
+/// Converts a size in bytes to a human readable string.
+fn format_size(size: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", size, units[0]),
+        _ => format!("{:.1} {}", value, units[unit]),
+    }
+}
+
+/// Describes size and modification time of a file.
+fn describe(path: &Path) -> String {
+    match std::fs::metadata(path) {
+        Err(_) => String::from("unknown size"),
+        Ok(meta) => {
+            let mut desc = format_size(meta.len());
+
+            if let Ok(modified) = meta.modified() {
+                let modified: DateTime<Local> = modified.into();
+                desc.push_str(&format!(", modified {}", modified.format("%Y-%m-%d %H:%M")));
+            }
+
+            desc
+        }
+    }
+}
+
+/// Reads a single, trimmed line from stdin. Returns None if stdin is closed.
+fn read_line() -> Option<String> {
+    let mut line = String::new();
+
+    match std::io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => Option::None,
+        Ok(_) => Option::Some(line.trim().to_string()),
+    }
+}
+
+impl Prompt {
+    /// Creates a new prompt without any remembered answers.
+    pub fn new() -> Self {
+        Prompt {
+            remembered: HashMap::new(),
+        }
+    }
+
+    /// Asks what to do with a file, answers given for its extension are re-used.
+    pub fn decide(&mut self, path: &Path) -> Decision {
+        let extension = path.extension().unwrap_or_default().to_os_string();
+
+        if let Some(decision) = self.remembered.get(&extension) {
+            return decision.clone();
+        }
+
+        loop {
+            print!(
+                "{} {:?} ({})\n  [m]ove, [s]kip, [M]ove all .{ext}, [S]kip all .{ext}, [c]hange destination, [q]uit: ",
+                "?".cyan(),
+                path.file_name().unwrap_or_default(),
+                describe(path),
+                ext = extension.to_string_lossy()
+            );
+            std::io::stdout().flush().ok();
+
+            let answer = match read_line() {
+                None => return Decision::Quit,
+                Some(answer) => answer,
+            };
+
+            match answer.as_str() {
+                "m" => return Decision::Move,
+                "s" => return Decision::Skip,
+                "q" => return Decision::Quit,
+                "M" | "S" => {
+                    let decision = match answer.as_str() {
+                        "M" => Decision::Move,
+                        _ => Decision::Skip,
+                    };
+
+                    self.remembered.insert(extension, decision.clone());
+                    return decision;
+                }
+                "c" => {
+                    print!("  Destination: ");
+                    std::io::stdout().flush().ok();
+
+                    match read_line() {
+                        None => return Decision::Quit,
+                        Some(dir) if dir.is_empty() => continue,
+                        Some(dir) => {
+                            let dir = PathBuf::from(dir);
+
+                            return Decision::MoveTo(dir.absolutize().unwrap_or(dir));
+                        }
+                    }
+                }
+                _ => println!("{} Unknown answer: {:?}", "✖".red(), answer),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prompt::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(10 * 1024 * 1024), "10.0 MiB");
+    }
+}
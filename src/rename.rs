@@ -0,0 +1,294 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local};
+
+/// Characters which aren't allowed in file names on Windows or SMB shares.
+const ILLEGAL_CHARACTERS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Characters separating directories, they'd move files out of their destination directory.
+const SEPARATORS: &[char] = &['/', '\\'];
+
+/// ASCII replacements for common non-ASCII characters.
+const TRANSLITERATIONS: &[(&str, &str)] = &[
+    ("ÀÁÂÃÅĀĂĄ", "A"),
+    ("àáâãåāăą", "a"),
+    ("ÇĆĈĊČ", "C"),
+    ("çćĉċč", "c"),
+    ("ĎĐ", "D"),
+    ("ďđ", "d"),
+    ("ÈÉÊËĒĔĖĘĚ", "E"),
+    ("èéêëēĕėęě", "e"),
+    ("ĜĞĠĢ", "G"),
+    ("ĝğġģ", "g"),
+    ("ÌÍÎÏĨĪĬĮİ", "I"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ŁĹĻĽ", "L"),
+    ("łĺļľ", "l"),
+    ("ÑŃŅŇ", "N"),
+    ("ñńņň", "n"),
+    ("ÒÓÔÕØŌŎŐ", "O"),
+    ("òóôõøōŏő", "o"),
+    ("ŔŖŘ", "R"),
+    ("ŕŗř", "r"),
+    ("ŚŜŞŠ", "S"),
+    ("śŝşš", "s"),
+    ("ŢŤ", "T"),
+    ("ţť", "t"),
+    ("ÙÚÛŨŪŬŮŰŲ", "U"),
+    ("ùúûũūŭůűų", "u"),
+    ("ÝŸ", "Y"),
+    ("ýÿ", "y"),
+    ("ŹŻŽ", "Z"),
+    ("źżž", "z"),
+    ("Ä", "Ae"),
+    ("ä", "ae"),
+    ("Ö", "Oe"),
+    ("ö", "oe"),
+    ("Ü", "Ue"),
+    ("ü", "ue"),
+    ("ß", "ss"),
+    ("Æ", "AE"),
+    ("æ", "ae"),
+    ("Œ", "OE"),
+    ("œ", "oe"),
+];
+
+/// Transforms applied to the name of a file while it's moved.
+/// They're always applied in the order of the fields, the extension is only lowercased.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rename {
+    /// Decodes percent-encoded characters, e.g. `%20`.
+    pub url_decode: bool,
+
+    /// Replaces non-ASCII characters by their closest ASCII equivalent, others are removed.
+    pub ascii: bool,
+
+    /// Removes characters which are illegal on Windows or SMB shares.
+    pub strip_illegal: bool,
+
+    /// Replaces whitespace by the contained character.
+    pub spaces: Option<char>,
+
+    /// Converts the name to lowercase.
+    pub lowercase: bool,
+
+    /// Prefixes the name with the date the file got last modified, e.g. `2020-02-03_`.
+    pub date_prefix: bool,
+
+    /// Maximum amount of characters the name, including the extension, can have.
+    pub max_length: Option<usize>,
+}
+
+/// Decodes percent-encoded characters, returns None if the result isn't valid UTF-8.
+fn url_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = s.get(i + 1..i + 3).filter(|_| bytes[i] == b'%');
+
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// Replaces non-ASCII characters by their closest ASCII equivalent, others are removed.
+fn transliterate(s: &str) -> String {
+    s.chars()
+        .filter_map(|c| match c.is_ascii() {
+            true => Option::Some(c.to_string()),
+            false => TRANSLITERATIONS
+                .iter()
+                .find(|(chars, _)| chars.contains(c))
+                .map(|(_, replacement)| replacement.to_string()),
+        })
+        .collect()
+}
+
+impl Rename {
+    /// Checks whenever no transform is enabled.
+    pub fn is_empty(&self) -> bool {
+        *self == Rename::default()
+    }
+
+    /// Transforms a file name without extension.
+    fn transform_stem(&self, stem: &str, path: &Path) -> String {
+        let mut stem = stem.to_string();
+
+        if self.url_decode {
+            stem = url_decode(&stem).unwrap_or(stem);
+        }
+
+        if self.ascii {
+            stem = transliterate(&stem);
+        }
+
+        if self.strip_illegal {
+            stem = stem
+                .chars()
+                .filter(|c| !c.is_control() && !ILLEGAL_CHARACTERS.contains(c))
+                .collect::<String>()
+                .trim_end_matches(['.', ' '])
+                .to_string();
+        }
+
+        if let Some(replacement) = self.spaces {
+            stem = stem
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(&replacement.to_string());
+        }
+
+        if self.lowercase {
+            stem = stem.to_lowercase();
+        }
+
+        if self.date_prefix {
+            let modified = std::fs::metadata(path).and_then(|meta| meta.modified());
+
+            if let Ok(modified) = modified {
+                let date: DateTime<Local> = modified.into();
+                stem = format!("{}_{}", date.format("%Y-%m-%d"), stem);
+            }
+        }
+
+        // Decoded names like ..%2F..%2Fx must stay within the destination directory
+        stem.retain(|c| !SEPARATORS.contains(&c));
+        match stem.as_str() {
+            "." | ".." => String::new(),
+            _ => stem,
+        }
+    }
+
+    /// Returns the new name of a file, names which aren't valid UTF-8 or would end up empty
+    /// are kept as they are.
+    pub fn file_name(&self, path: &Path) -> OsString {
+        let name = path.file_name().unwrap_or_default();
+        let (stem, extension) = match (name.to_str(), path.extension()) {
+            (Some(name), Some(extension)) => {
+                let extension = extension.to_str().unwrap();
+                (
+                    &name[..name.len() - extension.len() - 1],
+                    Option::Some(extension),
+                )
+            }
+            (Some(name), None) => (name, Option::None),
+            (None, _) => return name.to_os_string(),
+        };
+
+        let mut stem = self.transform_stem(stem, path);
+        let extension = match extension {
+            Some(extension) if self.lowercase => format!(".{}", extension.to_lowercase()),
+            Some(extension) => format!(".{}", extension),
+            None => String::new(),
+        };
+
+        if let Some(max_length) = self.max_length {
+            let length = max_length.saturating_sub(extension.chars().count());
+            stem = stem.chars().take(length).collect();
+        }
+
+        let renamed = stem.clone() + &extension;
+        match stem.trim().is_empty() || renamed == "." || renamed == ".." {
+            true => name.to_os_string(),
+            false => OsString::from(renamed),
+        }
+    }
+}
+
+impl FromStr for Rename {
+    type Err = String;
+
+    /// Parses a comma-separated list of transforms, e.g. `lowercase,spaces=-,max-length=64`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rename = Rename::default();
+
+        for transform in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, value) = match transform.split_once('=') {
+                Some((name, value)) => (name, Option::Some(value)),
+                None => (transform, Option::None),
+            };
+
+            match (name, value) {
+                ("url-decode", None) => rename.url_decode = true,
+                ("ascii", None) => rename.ascii = true,
+                ("strip-illegal", None) => rename.strip_illegal = true,
+                ("lowercase", None) => rename.lowercase = true,
+                ("date-prefix", None) => rename.date_prefix = true,
+                ("spaces", Some(value)) => {
+                    let mut chars = value.chars();
+                    rename.spaces = match (chars.next(), chars.next()) {
+                        (Some(c), None) if !SEPARATORS.contains(&c) => Option::Some(c),
+                        _ => return Err(format!("Invalid replacement for spaces: {}", value)),
+                    };
+                }
+                ("max-length", Some(value)) => match value.parse() {
+                    Ok(length) if length > 0 => rename.max_length = Option::Some(length),
+                    _ => return Err(format!("Invalid maximum length: {}", value)),
+                },
+                _ => return Err(format!("Unknown transform: {}", transform)),
+            }
+        }
+
+        Ok(rename)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rename::*;
+
+    fn renamed(transforms: &str, name: &str) -> String {
+        let rename: Rename = transforms.parse().unwrap();
+        rename.file_name(Path::new(name)).into_string().unwrap()
+    }
+
+    #[test]
+    fn transforms() {
+        assert_eq!(renamed("lowercase", "My File.JPG"), "my file.jpg");
+        assert_eq!(renamed("spaces=-", "My  File .pdf"), "My-File.pdf");
+        assert_eq!(
+            renamed("url-decode,spaces=_", "My%20File%2.pdf"),
+            "My_File%2.pdf"
+        );
+        assert_eq!(renamed("strip-illegal", "a<b>:c?.txt"), "abc.txt");
+        assert_eq!(renamed("ascii", "Grüße ☺ né.txt"), "Gruesse  ne.txt");
+        assert_eq!(renamed("max-length=8", "abcdefghij.txt"), "abcd.txt");
+        assert_eq!(renamed("ascii", "☺.txt"), "☺.txt");
+        assert_eq!(renamed("lowercase", "README"), "readme");
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(renamed("url-decode", "..%2F..%2Fx.txt"), "....x.txt");
+        assert_eq!(renamed("url-decode", "a%5Cb.txt"), "ab.txt");
+        assert_eq!(renamed("url-decode", "%2E%2E"), "%2E%2E");
+        assert_eq!(renamed("url-decode", "..%2F"), "..%2F");
+        assert_eq!(renamed("max-length=2", "..abc"), "..abc");
+        assert_eq!(renamed("max-length=1", ".abc"), ".abc");
+    }
+
+    #[test]
+    fn parse() {
+        assert!("lowercase,spaces=-,max-length=10".parse::<Rename>().is_ok());
+        assert!("uppercase".parse::<Rename>().is_err());
+        assert!("spaces=--".parse::<Rename>().is_err());
+        assert!("spaces=/".parse::<Rename>().is_err());
+        assert!("spaces=\\".parse::<Rename>().is_err());
+        assert!("max-length=0".parse::<Rename>().is_err());
+        assert!("".parse::<Rename>().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::file::{move_file_to, FileResult};
+use crate::log::parse_moves;
+use crate::sorter::{Event, Summary};
+use crate::utils::{collect_files, resolve_directories};
+
+/// Moves the files of a sorted archive, e.g. `<archive>/<extension>/<file>`, back into a single
+/// directory. Extension directories are removed once they're empty, files directly inside of
+/// the archive (such as the log-file) and hidden directories are left untouched.
+/// Renamed files get their original name back if they're listed in the log-file.
+pub struct Restorer {
+    archive: PathBuf,
+    destination: PathBuf,
+    log_file: Option<PathBuf>,
+    dry_run: bool,
+}
+
+/// Removes a directory and all of its sub-directories if they're empty.
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+
+    // Fails if the directory isn't empty, which is fine
+    std::fs::remove_dir(dir).ok();
+}
+
+impl Restorer {
+    /// Creates a new restorer moving the files of `archive` back into `destination`.
+    pub fn new<A: Into<PathBuf>, D: Into<PathBuf>>(archive: A, destination: D) -> Self {
+        Restorer {
+            archive: archive.into(),
+            destination: destination.into(),
+            log_file: Option::None,
+            dry_run: false,
+        }
+    }
+
+    /// Sets the log-file used to resolve the original names of renamed files.
+    pub fn log_file<P: Into<PathBuf>>(mut self, log_file: P) -> Self {
+        self.log_file = Option::Some(log_file.into());
+        self
+    }
+
+    /// Only checks which files would be moved, without moving them.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Resolves the original names of files according to the log-file.
+    fn original_names(&self) -> HashMap<PathBuf, OsString> {
+        let content = self
+            .log_file
+            .as_ref()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .unwrap_or_default();
+
+        parse_moves(&content)
+            .into_iter()
+            .filter_map(|entry| Option::Some((entry.to, entry.from.file_name()?.to_os_string())))
+            .collect()
+    }
+
+    /// Restores the archive, calling `callback` for everything that happens.
+    /// The archive is reported as only source in [`Event::Resolved`], the destination as target.
+    /// Fails if the archive does not exist, the destination couldn't be created or the archive
+    /// couldn't be read.
+    pub fn run<F: FnMut(Event)>(&self, mut callback: F) -> Result<Summary, Error> {
+        let (sources, destination) = resolve_directories(
+            std::slice::from_ref(&self.archive),
+            &self.destination,
+            self.dry_run,
+        )?;
+        let archive = sources.into_iter().next().unwrap();
+
+        callback(Event::Resolved {
+            sources: vec![archive.clone()],
+            target: destination.clone(),
+            routes: Vec::new(),
+        });
+
+        let entries = match std::fs::read_dir(&archive) {
+            Ok(entries) => entries,
+            Err(e) => return Err(Error::ReadDir(archive, e)),
+        };
+
+        let original_names = self.original_names();
+        let mut summary = Summary::default();
+        for entry in entries {
+            let dir = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    summary.processed += 1;
+                    summary.errored += 1;
+                    callback(Event::Failed(e));
+                    continue;
+                }
+            };
+
+            let hidden = dir
+                .file_name()
+                .is_none_or(|name| name.to_string_lossy().starts_with('.'));
+
+            // Only extension directories are restored
+            if hidden || !dir.is_dir() || dir == destination {
+                continue;
+            }
+
+            let mut files = Vec::new();
+            let errored = collect_files(&dir, &mut files, &mut callback);
+            summary.processed += errored;
+            summary.errored += errored;
+
+            for path in files {
+                let result = match self.dry_run {
+                    true => FileResult::Checked,
+                    false => {
+                        let name = match original_names.get(&path) {
+                            Some(name) => name.as_os_str(),
+                            None => path.file_name().unwrap(),
+                        };
+
+                        move_file_to(&path, &destination.join(name))
+                    }
+                };
+
+                summary.processed += 1;
+
+                if let FileResult::Errored(_) = &result {
+                    summary.errored += 1;
+                }
+
+                callback(Event::Processed {
+                    source: archive.clone(),
+                    path,
+                    result,
+                });
+            }
+
+            if !self.dry_run {
+                remove_empty_dirs(&dir);
+            }
+        }
+
+        Ok(summary)
+    }
+}
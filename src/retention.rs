@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::error::{Error, FileError, FileErrorKind};
+use crate::file::FileResult;
+use crate::log::parse_moves;
+use crate::sorter::{Event, Summary};
+use crate::trash::Trash;
+use crate::utils::{collect_files, resolve_directories};
+
+/// Why a file expired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpiryReason {
+    /// The file has been archived longer than the maximum age, contains how long ago.
+    Age(Duration),
+
+    /// There are more newer files than the contained amount in the same category.
+    Count(usize),
+
+    /// The archive would be larger than the contained amount of bytes otherwise.
+    Size(u64),
+}
+
+impl fmt::Display for ExpiryReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpiryReason::Age(age) => write!(f, "archived {} days ago", age.as_secs() / 86400),
+            ExpiryReason::Count(count) => write!(f, "not within the {} newest", count),
+            ExpiryReason::Size(size) => write!(f, "archive exceeds {} bytes", size),
+        }
+    }
+}
+
+/// A file inside of the archive.
+struct ArchivedFile {
+    path: PathBuf,
+    category: PathBuf,
+    archived: SystemTime,
+    size: u64,
+}
+
+/// Removes files from a sorted archive, e.g. `<archive>/<extension>/<file>`, according to a policy.
+/// The time a file got archived is taken from the log-file, its modification time is used if
+/// it's not listed in there. Files are moved to the trash unless disabled.
+pub struct Retention {
+    archive: PathBuf,
+    log_file: Option<PathBuf>,
+    max_age: Option<Duration>,
+    keep_newest: Option<usize>,
+    max_size: Option<u64>,
+    dry_run: bool,
+    trash: Option<Trash>,
+}
+
+impl Retention {
+    /// Creates a new retention-policy for an archive which doesn't expire anything yet.
+    pub fn new<P: Into<PathBuf>>(archive: P) -> Self {
+        Retention {
+            archive: archive.into(),
+            log_file: Option::None,
+            max_age: Option::None,
+            keep_newest: Option::None,
+            max_size: Option::None,
+            dry_run: false,
+            trash: Option::Some(Trash::new()),
+        }
+    }
+
+    /// Sets the log-file used to resolve when files got archived.
+    pub fn log_file<P: Into<PathBuf>>(mut self, log_file: P) -> Self {
+        self.log_file = Option::Some(log_file.into());
+        self
+    }
+
+    /// Expires files which have been archived longer than `max_age` ago.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Option::Some(max_age);
+        self
+    }
+
+    /// Keeps only the newest `count` files of each category.
+    pub fn keep_newest(mut self, count: usize) -> Self {
+        self.keep_newest = Option::Some(count);
+        self
+    }
+
+    /// Expires the oldest files until the archive is at most `bytes` large.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Option::Some(bytes);
+        self
+    }
+
+    /// Only checks which files would expire, without removing them.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets the trash expired files are moved to, they're removed permanently if None.
+    pub fn trash(mut self, trash: Option<Trash>) -> Self {
+        self.trash = trash;
+        self
+    }
+
+    /// Removes a single file, either by moving it to the trash or permanently.
+    fn remove(&self, path: &Path, reason: ExpiryReason) -> FileResult {
+        match &self.trash {
+            Some(trash) => match trash.put(path) {
+                Ok(dest) => FileResult::Trashed(dest, reason),
+                Err(e) => {
+                    let dest = trash.home().unwrap_or(path);
+                    FileResult::Errored(FileError::from_io(
+                        FileErrorKind::TrashFailed,
+                        path,
+                        dest,
+                        e,
+                    ))
+                }
+            },
+            None => match std::fs::remove_file(path) {
+                Ok(_) => FileResult::Deleted(reason),
+                Err(e) => FileResult::Errored(FileError::from_io(
+                    FileErrorKind::RemoveFailed,
+                    path,
+                    path,
+                    e,
+                )),
+            },
+        }
+    }
+
+    /// Resolves when files got archived according to the log-file.
+    fn archive_times(&self) -> HashMap<PathBuf, SystemTime> {
+        let content = self
+            .log_file
+            .as_ref()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .unwrap_or_default();
+
+        // Later moves override earlier ones
+        parse_moves(&content)
+            .into_iter()
+            .map(|entry| (entry.to, entry.time.into()))
+            .collect()
+    }
+
+    /// Collects the files of all categories, newest first.
+    fn collect<F: FnMut(Event)>(
+        &self,
+        archive: &Path,
+        summary: &mut Summary,
+        callback: &mut F,
+    ) -> Result<Vec<ArchivedFile>, Error> {
+        let entries = match std::fs::read_dir(archive) {
+            Ok(entries) => entries,
+            Err(e) => return Err(Error::ReadDir(archive.to_path_buf(), e)),
+        };
+
+        let archive_times = self.archive_times();
+        let mut files = Vec::new();
+
+        for entry in entries.flatten() {
+            let category = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+            // Only category directories contain archived files
+            if hidden || !category.is_dir() {
+                continue;
+            }
+
+            let mut paths = Vec::new();
+            let errored = collect_files(&category, &mut paths, callback);
+            summary.processed += errored;
+            summary.errored += errored;
+
+            for path in paths {
+                let meta = match std::fs::metadata(&path) {
+                    Ok(meta) => meta,
+                    Err(_) => continue,
+                };
+
+                let archived = match archive_times.get(&path) {
+                    Some(time) => *time,
+                    None => meta.modified().unwrap_or_else(|_| SystemTime::now()),
+                };
+
+                files.push(ArchivedFile {
+                    path,
+                    category: category.clone(),
+                    archived,
+                    size: meta.len(),
+                });
+            }
+        }
+
+        files.sort_by_key(|file| std::cmp::Reverse(file.archived));
+        Ok(files)
+    }
+
+    /// Decides which of the files, sorted newest first, expire.
+    fn expire(&self, files: &[ArchivedFile]) -> Vec<Option<ExpiryReason>> {
+        let now = SystemTime::now();
+        let mut per_category: HashMap<&Path, usize> = HashMap::new();
+        let mut total_size: u64 = 0;
+
+        files
+            .iter()
+            .map(|file| {
+                let age = now.duration_since(file.archived).unwrap_or_default();
+
+                if self.max_age.is_some_and(|max_age| age > max_age) {
+                    return Option::Some(ExpiryReason::Age(age));
+                }
+
+                let kept = per_category.entry(&file.category).or_insert(0);
+                if let Some(count) = self.keep_newest.filter(|count| *kept >= *count) {
+                    return Option::Some(ExpiryReason::Count(count));
+                }
+
+                if let Some(size) = self.max_size.filter(|size| total_size + file.size > *size) {
+                    return Option::Some(ExpiryReason::Size(size));
+                }
+
+                *kept += 1;
+                total_size += file.size;
+                Option::None
+            })
+            .collect()
+    }
+
+    /// Applies the policy, calling `callback` for every file which expired.
+    /// The archive is reported as only source in [`Event::Resolved`] and as target.
+    /// Fails if the archive does not exist or couldn't be read.
+    pub fn run<F: FnMut(Event)>(&self, mut callback: F) -> Result<Summary, Error> {
+        let (sources, archive) =
+            resolve_directories(std::slice::from_ref(&self.archive), &self.archive, true)?;
+
+        callback(Event::Resolved {
+            sources,
+            target: archive.clone(),
+            routes: Vec::new(),
+        });
+
+        let mut summary = Summary::default();
+        let files = self.collect(&archive, &mut summary, &mut callback)?;
+
+        for (file, reason) in files.iter().zip(self.expire(&files)) {
+            let reason = match reason {
+                Some(reason) => reason,
+                None => continue,
+            };
+
+            let result = match self.dry_run {
+                true => FileResult::Expired(reason),
+                false => self.remove(&file.path, reason),
+            };
+
+            summary.processed += 1;
+
+            if let FileResult::Errored(_) = result {
+                summary.errored += 1;
+            }
+
+            callback(Event::Processed {
+                source: archive.clone(),
+                path: file.path.clone(),
+                result,
+            });
+        }
+
+        Ok(summary)
+    }
+}
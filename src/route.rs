@@ -0,0 +1,96 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Sends files with one of the extensions into a directory other than the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Extensions of the files, lowercase.
+    pub extensions: Vec<String>,
+
+    /// Directory the files are moved into.
+    pub directory: PathBuf,
+}
+
+impl Route {
+    /// Creates a new route.
+    pub fn new<P: Into<PathBuf>>(extensions: &[&str], directory: P) -> Self {
+        Route {
+            extensions: extensions.iter().map(|s| s.to_lowercase()).collect(),
+            directory: directory.into(),
+        }
+    }
+
+    /// Checks whenever files with `extension` are sent along this route.
+    pub fn matches(&self, extension: &str) -> bool {
+        self.extensions.contains(&extension.to_lowercase())
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {:?}", self.extensions.join(", "), self.directory)
+    }
+}
+
+impl FromStr for Route {
+    type Err = String;
+
+    /// Parses a route, e.g. `iso,img=/mnt/bulk/isos`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (extensions, directory) = match s.split_once('=') {
+            Some(route) => route,
+            None => {
+                return Err(format!(
+                    "Invalid route, expected 'extensions=directory': {}",
+                    s
+                ))
+            }
+        };
+
+        let extensions: Vec<String> = extensions
+            .split(',')
+            .map(|s| s.trim().trim_start_matches('.').to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let directory = PathBuf::from(directory.trim());
+        if extensions.is_empty() || directory.as_os_str().is_empty() {
+            return Err(format!("Route without extensions or directory: {}", s));
+        }
+
+        // Relative directories would depend on where cleanup is run from
+        match directory.is_absolute() {
+            true => Ok(Route {
+                extensions,
+                directory,
+            }),
+            false => Err(format!("Route directory must be absolute: {}", s)),
+        }
+    }
+}
+
+/**
+ * Returns the directory of the first route matching `extension`, if any.
+ */
+pub fn routed<'a>(routes: &'a [Route], extension: &str) -> Option<&'a Path> {
+    routes
+        .iter()
+        .find(|route| route.matches(extension))
+        .map(|route| route.directory.as_path())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::route::*;
+
+    #[test]
+    fn parse() {
+        let route: Route = "ISO, .img=/mnt/bulk/isos".parse().unwrap();
+        assert_eq!(route, Route::new(&["iso", "img"], "/mnt/bulk/isos"));
+        assert_eq!(routed(&[route], "img"), Some(Path::new("/mnt/bulk/isos")));
+        assert!("pdf".parse::<Route>().is_err());
+        assert!("=/srv/docs".parse::<Route>().is_err());
+        assert!("pdf=docs".parse::<Route>().is_err());
+    }
+}
@@ -1,121 +1,165 @@
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use colored::Colorize;
 
-use crate::cli::result::CLIResult;
-use crate::file::{accept, FileResult, Options};
-use crate::lib::resolve_directories;
+use cleanup_files::log::format_line;
+use cleanup_files::{Error, Event, FileResult, Restorer, Retention, Sorter};
 
-fn resolve_cs_list(val: Option<&String>) -> Option<Vec<String>> {
-    if val.is_none() {
-        return Option::None;
-    }
+use crate::prompt::Prompt;
 
-    Option::Some(val.unwrap().split(",").map(|s| s.to_string()).collect())
+/// A processed file which will be written to the log-file.
+struct LogEntry {
+    time: DateTime<Utc>,
+    source: PathBuf,
+    path: PathBuf,
+    result: FileResult,
 }
 
-pub fn start(app: CLIResult) {
-    // Resolve current executable to prevent sorting it
-    let current_exe = std::env::current_exe()
-        .ok()
-        .expect("Failed to resolve current executable.");
-
-    // Source and target directory
-    let (source, target) = resolve_directories(&app);
-
-    println!(
-        "Using the following paths:\n | Source: {:?}\n | Target: {:?}\n",
-        source, target
-    );
+/// Prints what happens and collects it for the log-file.
+#[derive(Default)]
+struct Reporter {
+    sources: Vec<PathBuf>,
+    target: PathBuf,
+    log: Vec<LogEntry>,
+}
 
-    let options = Options {
-        dry_run: app.has_flag("dry"),
-        excluded: resolve_cs_list(app.get_arg("excluded")),
-        included: resolve_cs_list(app.get_arg("included")),
-    };
+impl Reporter {
+    /// Prints an event and keeps processed files for the log-file.
+    fn report(&mut self, event: Event) {
+        match event {
+            Event::Resolved {
+                sources,
+                target,
+                routes,
+            } => {
+                println!("Using the following paths:");
+                for source in &sources {
+                    println!(" | Source: {:?}", source);
+                }
 
-    // Parse arguments and read directory entries
-    let dir = std::fs::read_dir(&source)
-        .ok()
-        .expect(&format!("Failed to read directory: {:?}", source));
-
-    // Log
-    let mut log: Vec<(FileResult, String)> = Vec::new();
-    for result in dir {
-        match result {
-            Err(error) => println!("{}", error),
-            Ok(entry) => {
-                let path = entry.path();
-                let raw_path = String::from(path.to_str().unwrap());
-
-                // Skipped current file and other non-file entries
-                if path.eq(&current_exe) || !path.is_file() {
-                    continue;
+                println!(" | Target: {:?}", target);
+                for route in &routes {
+                    println!(" | Route: {}", route);
                 }
 
-                let res = accept(&path, &target, &options);
+                println!();
+                self.sources = sources;
+                self.target = target;
+            }
+            Event::Failed(error) => eprintln!("{} {}", "✖ Errored:".red(), error),
+            Event::Processed {
+                source,
+                path,
+                result,
+            } => {
+                let raw_path = path.display();
 
                 // Print message
-                match &res {
-                    FileResult::Errored(error) => println!("{} {}", "✖ Errored:".red(), error),
+                match &result {
+                    FileResult::Errored(error) => eprintln!("{} {}", "✖ Errored:".red(), error),
                     FileResult::Moved(_) => println!("{} {}", "♻ Moved:".green(), raw_path),
                     FileResult::Skipped => println!("{} {}", "⊙ Skipped:".yellow(), raw_path),
+                    FileResult::Ignored(rule) => {
+                        println!("{} {} ({})", "⊙ Skipped:".yellow(), raw_path, rule)
+                    }
+                    FileResult::Busy(reason) => {
+                        println!("{} {} ({})", "⧗ Busy:".yellow(), raw_path, reason)
+                    }
                     FileResult::Checked => println!("{} {}", "✔ Matched:".cyan(), raw_path),
+                    FileResult::Planned(dest) => {
+                        println!("{} {} -> {}", "✔ Matched:".cyan(), raw_path, dest.display())
+                    }
+                    FileResult::Deleted(reason) => {
+                        println!("{} {} ({})", "⌫ Deleted:".magenta(), raw_path, reason)
+                    }
+                    FileResult::Trashed(_, reason) => {
+                        println!("{} {} ({})", "⌫ Trashed:".magenta(), raw_path, reason)
+                    }
+                    FileResult::Expired(reason) => {
+                        println!("{} {} ({})", "✔ Expired:".cyan(), raw_path, reason)
+                    }
+                    _ => println!("{} {}", "✔ Processed:".cyan(), raw_path),
                 };
 
                 // Push to logs
-                log.push((res, raw_path));
+                self.log.push(LogEntry {
+                    time: Utc::now(),
+                    source,
+                    path,
+                    result,
+                });
             }
-        };
+        }
     }
 
-    // Don't create a log-file if a dry-run is being performed
-    if !app.has_flag("dry") {
-        let default_log_file = &String::from("cleanup.log");
-        let log_file = app
-            .get_arg("log")
-            .or(Option::Some(default_log_file))
-            .unwrap();
+    /// Appends everything processed to `log_file`, relative to `dir`.
+    fn finish(self, dir: &Path, log_file: Option<&str>) -> Result<(), Error> {
+        if let Some(log_file) = log_file {
+            let log_file_path = dir.join(log_file);
+            if let Err(e) = write_log(&log_file_path, self.log) {
+                return Err(Error::LogFile(log_file_path, e));
+            }
 
-        if log_file.eq("false") {
-            return;
+            println!("{} {:?}", "⚙ Log file updated:".cyan(), &log_file_path);
         }
 
-        let log_file_path = target.join(log_file);
-        if !log_file_path.exists() {
-            std::fs::write(&log_file_path, "")
-                .ok()
-                .expect(&format!("Failed to create log-file {:?}", log_file_path))
-        }
+        Ok(())
+    }
+}
 
-        let mut log_file = OpenOptions::new()
-            .append(true)
-            .open(&log_file_path)
-            .unwrap();
-
-        for (res, path) in log {
-            let mut content = Utc::now().format("%Y-%m-%d %H:%M:%S ").to_string();
-            content.push_str(
-                (match res {
-                    FileResult::Errored(error) => format!("[ERRORED] {} ({})", path, error),
-                    FileResult::Moved(dest) => {
-                        format!("[MOVED] {} -> {}", path, dest.to_str().unwrap())
-                    }
-                    FileResult::Skipped => format!("[SKIPPED] {}", path),
-                    FileResult::Checked => format!("[CHECKED] {}", path),
-                })
-                .as_str(),
-            );
-
-            log_file
-                .write(content.as_bytes())
-                .and(log_file.write("\n".as_bytes()))
-                .ok()
-                .expect(&format!("Failed to update log-file {:?}", log_file_path));
-        }
+/// Runs the sorter, prints what happened and appends it to `log_file` (relative to the target).
+/// Asks before moving each file if `interactive` is set.
+pub fn start(sorter: &Sorter, log_file: Option<&str>, interactive: bool) -> Result<(), Error> {
+    let mut reporter = Reporter::default();
+
+    let summary = if interactive {
+        let mut prompt = Prompt::new();
+        sorter.run_with(|path| prompt.decide(path), |event| reporter.report(event))?
+    } else {
+        sorter.run(|event| reporter.report(event))?
+    };
+
+    let target = reporter.target.clone();
+    reporter.finish(&target, log_file)?;
+    summary.status()
+}
 
-        println!("{} {:?}", "⚙ Log file updated:".cyan(), &log_file_path);
+/// Moves the files of an archive back, prints what happened and appends it to `log_file`
+/// (relative to the archive).
+pub fn restore(restorer: &Restorer, log_file: Option<&str>) -> Result<(), Error> {
+    let mut reporter = Reporter::default();
+    let summary = restorer.run(|event| reporter.report(event))?;
+
+    let archive = reporter.sources[0].clone();
+    reporter.finish(&archive, log_file)?;
+    summary.status()
+}
+
+/// Removes expired files from an archive, prints what happened and appends it to `log_file`
+/// (relative to the archive).
+pub fn expire(retention: &Retention, log_file: Option<&str>) -> Result<(), Error> {
+    let mut reporter = Reporter::default();
+    let summary = retention.run(|event| reporter.report(event))?;
+
+    let archive = reporter.target.clone();
+    reporter.finish(&archive, log_file)?;
+    summary.status()
+}
+
+/// Appends the results of a run to the log-file.
+fn write_log(log_file_path: &Path, log: Vec<LogEntry>) -> std::io::Result<()> {
+    let mut log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file_path)?;
+
+    for entry in log {
+        let line = format_line(&entry.time, &entry.source, &entry.path, &entry.result);
+        writeln!(log_file, "{}", line)?;
     }
+
+    Ok(())
 }
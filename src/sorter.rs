@@ -0,0 +1,259 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::busy::{self, OpenFiles};
+use crate::error::{Error, FileError, FileErrorKind};
+use crate::file::{accept, FileResult, Options};
+use crate::ignore::{IgnoreFile, IGNORE_FILE};
+use crate::route::Route;
+use crate::utils::{resolve_directories, resolve_routes};
+
+/// Something that happened while sorting, passed to the callback of [`Sorter::run`].
+#[derive(Debug)]
+pub enum Event {
+    /// Sources, target and the directories of routes have been resolved to absolute paths.
+    Resolved {
+        sources: Vec<PathBuf>,
+        target: PathBuf,
+        routes: Vec<Route>,
+    },
+
+    /// A file has been processed, `source` is the source directory it came from.
+    Processed {
+        source: PathBuf,
+        path: PathBuf,
+        result: FileResult,
+    },
+
+    /// A directory entry couldn't be read.
+    Failed(io::Error),
+}
+
+/// What to do with a file, returned by the `decide` function of [`Sorter::run_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Move the file into the target directory.
+    Move,
+
+    /// Move the file into a different target directory.
+    MoveTo(PathBuf),
+
+    /// Leave the file where it is.
+    Skip,
+
+    /// Stop, the remaining files are left untouched.
+    Quit,
+}
+
+/// Counts of a finished run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Amount of entries processed, including the ones which errored.
+    pub processed: usize,
+
+    /// Amount of entries which errored.
+    pub errored: usize,
+}
+
+impl Summary {
+    /// Converts this summary into a result.
+    /// Fails with [`Error::TotalFailure`] if every entry errored and with
+    /// [`Error::PartialFailure`] if only some of them did.
+    pub fn status(&self) -> Result<(), Error> {
+        match self.errored {
+            0 => Ok(()),
+            errored if errored == self.processed => Err(Error::TotalFailure { errored }),
+            errored => Err(Error::PartialFailure {
+                errored,
+                total: self.processed,
+            }),
+        }
+    }
+}
+
+/// Sorts the files of one or more source directories into `<target>/<extension>/<file>`.
+/// Hidden files, directories, explicitly ignored paths and files matching
+/// the `.cleanupignore` of a source are left untouched.
+pub struct Sorter {
+    sources: Vec<PathBuf>,
+    target: Option<PathBuf>,
+    options: Options,
+    ignored: Vec<PathBuf>,
+}
+
+impl Sorter {
+    /// Creates a new sorter for the given source directory.
+    pub fn new<P: Into<PathBuf>>(source: P) -> Self {
+        Sorter {
+            sources: vec![source.into()],
+            target: Option::None,
+            options: Options::default(),
+            ignored: Vec::new(),
+        }
+    }
+
+    /// Adds another source directory.
+    pub fn source<P: Into<PathBuf>>(mut self, source: P) -> Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    /// Sets the target directory. Default is the first source + `.archive`.
+    pub fn target<P: Into<PathBuf>>(mut self, target: P) -> Self {
+        self.target = Option::Some(target.into());
+        self
+    }
+
+    /// Sets the options passed to [`accept`] for each file.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Excludes a specific path from being sorted, e.g. the running executable.
+    pub fn ignore<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.ignored.push(path.into());
+        self
+    }
+
+    /// Resolves the target directory.
+    fn resolve_target(&self) -> PathBuf {
+        match &self.target {
+            Some(target) => target.clone(),
+            None => self.sources[0].join(".archive"),
+        }
+    }
+
+    /// Checks whenever a directory entry should be left untouched.
+    fn is_ignored(&self, path: &Path) -> bool {
+        !path.is_file() || self.ignored.iter().any(|p| p == path)
+    }
+
+    /// Checks whenever a file would be moved.
+    /// Returns the final result if it's filtered out, busy, errored or a dry-run is performed.
+    fn check(&self, path: &Path, target: &Path, open_files: &OpenFiles) -> Option<FileResult> {
+        let options = Options {
+            dry_run: true,
+            ..self.options.clone()
+        };
+
+        match accept(path, target, &options) {
+            FileResult::Planned(dest) => match busy::check(path, &self.options, open_files) {
+                Some(reason) => Option::Some(FileResult::Busy(reason)),
+                None if self.options.dry_run => Option::Some(FileResult::Planned(dest)),
+                None => Option::None,
+            },
+            result => Option::Some(result),
+        }
+    }
+
+    /// Sorts the source directories, calling `callback` for everything that happens.
+    /// Fails if a source does not exist, the target couldn't be created or a
+    /// source couldn't be read. Errors of single files are reported via the callback
+    /// and counted in the returned summary.
+    pub fn run<F: FnMut(Event)>(&self, callback: F) -> Result<Summary, Error> {
+        self.run_with(|_| Decision::Move, callback)
+    }
+
+    /// Same as [`Sorter::run`] but calls `decide` for every file before it gets moved.
+    /// Files which are filtered out by the options aren't passed to `decide`.
+    pub fn run_with<D, F>(&self, mut decide: D, mut callback: F) -> Result<Summary, Error>
+    where
+        D: FnMut(&Path) -> Decision,
+        F: FnMut(Event),
+    {
+        let (sources, target) =
+            resolve_directories(&self.sources, &self.resolve_target(), self.options.dry_run)?;
+        let options = Options {
+            routes: resolve_routes(&self.options.routes, self.options.dry_run)?,
+            ..self.options.clone()
+        };
+
+        callback(Event::Resolved {
+            sources: sources.clone(),
+            target: target.clone(),
+            routes: options.routes.clone(),
+        });
+
+        // Resolving open files is expensive, it's done once for all files
+        let open_files = match self.options.busy_check {
+            true => OpenFiles::scan(),
+            false => OpenFiles::default(),
+        };
+
+        // Routes don't apply to destinations chosen explicitly
+        let explicit = Options {
+            routes: Vec::new(),
+            ..options.clone()
+        };
+
+        let mut summary = Summary::default();
+        'sources: for source in sources {
+            let dir = match std::fs::read_dir(&source) {
+                Ok(dir) => dir,
+                Err(e) => return Err(Error::ReadDir(source, e)),
+            };
+
+            let ignore_file = match IgnoreFile::load(&source) {
+                Ok(ignore_file) => ignore_file,
+                Err(e) => return Err(Error::IgnoreFile(source.join(IGNORE_FILE), e)),
+            };
+
+            for result in dir {
+                match result {
+                    Err(error) => {
+                        summary.processed += 1;
+                        summary.errored += 1;
+                        callback(Event::Failed(error));
+                    }
+                    Ok(entry) => {
+                        let path = entry.path();
+
+                        if self.is_ignored(&path) {
+                            continue;
+                        }
+
+                        // Files matching the ignore-file are never passed to decide
+                        let relative = path.strip_prefix(&source).unwrap_or(&path);
+                        let checked = match ignore_file.matched(relative, false) {
+                            Some(rule) => Option::Some(FileResult::Ignored(rule.clone())),
+                            None => self.check(&path, &target, &open_files),
+                        };
+
+                        let result = match checked {
+                            Some(result) => result,
+                            None => match decide(&path) {
+                                Decision::Move => accept(&path, &target, &options),
+                                Decision::MoveTo(other) => match std::fs::create_dir_all(&other) {
+                                    Ok(_) => accept(&path, &other, &explicit),
+                                    Err(e) => FileResult::Errored(FileError::from_io(
+                                        FileErrorKind::CreateDirFailed,
+                                        &path,
+                                        &other,
+                                        e,
+                                    )),
+                                },
+                                Decision::Skip => FileResult::Skipped,
+                                Decision::Quit => break 'sources,
+                            },
+                        };
+
+                        summary.processed += 1;
+
+                        if let FileResult::Errored(_) = &result {
+                            summary.errored += 1;
+                        }
+
+                        callback(Event::Processed {
+                            source: source.clone(),
+                            path,
+                            result,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+}
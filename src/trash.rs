@@ -0,0 +1,297 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+/// Characters which are kept as they are in the `Path` of a `.trashinfo` file.
+const UNRESERVED: &[u8] = b"-_.!~*'()/";
+
+/// The trash of the current user, following the FreeDesktop.org trash specification.
+/// Files on other devices than the home trash are moved to the trash of their mount point,
+/// e.g. `$topdir/.Trash/$uid` or `$topdir/.Trash-$uid`.
+#[derive(Debug, Clone)]
+pub struct Trash {
+    home: Option<PathBuf>,
+}
+
+/// A trash directory containing `files` and `info`.
+struct TrashDir {
+    /// The directory itself.
+    path: PathBuf,
+
+    /// The mount point the trash belongs to, paths in `.trashinfo` files are relative to it.
+    /// None for the home trash, which uses absolute paths.
+    top_dir: Option<PathBuf>,
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Trash::new()
+    }
+}
+
+impl Trash {
+    /// Resolves the home trash, e.g. `$XDG_DATA_HOME/Trash` or `~/.local/share/Trash`.
+    pub fn new() -> Self {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+
+        Trash {
+            home: data_home.map(|dir| dir.join("Trash")),
+        }
+    }
+
+    /// Uses a custom directory as home trash.
+    pub fn at<P: Into<PathBuf>>(home: P) -> Self {
+        Trash {
+            home: Option::Some(home.into()),
+        }
+    }
+
+    /// Returns the home trash, if it could be resolved.
+    pub fn home(&self) -> Option<&Path> {
+        self.home.as_deref()
+    }
+
+    /// Moves a file into the trash, returns where it's been moved to.
+    pub fn put(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = path.canonicalize()?;
+
+        // The first trash which can be used wins, e.g. $topdir/.Trash-$uid if $topdir/.Trash/$uid can't
+        let mut candidates = self.resolve(&path)?.into_iter().peekable();
+        let (trash, files, info) = loop {
+            let trash = match candidates.next() {
+                Some(trash) => trash,
+                None => return Err(io::Error::new(io::ErrorKind::NotFound, "trash not found")),
+            };
+
+            let files = trash.path.join("files");
+            let info = trash.path.join("info");
+            match std::fs::create_dir_all(&files).and_then(|_| std::fs::create_dir_all(&info)) {
+                Ok(_) => break (trash, files, info),
+                Err(e) if candidates.peek().is_none() => return Err(e),
+                Err(_) => continue,
+            }
+        };
+
+        let original = match &trash.top_dir {
+            Some(top_dir) => path.strip_prefix(top_dir).unwrap_or(&path),
+            None => &path,
+        };
+
+        let content = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(original),
+            Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        // The info-file is created first and exclusively to reserve the name
+        let name = path.file_name().unwrap();
+        for index in 1.. {
+            let name = trash_name(name, index);
+            let mut info_name = name.clone();
+            info_name.push(".trashinfo");
+
+            let info_path = info.join(info_name);
+            let mut info_file = match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+
+            let target = files.join(&name);
+            if target.exists() {
+                std::fs::remove_file(&info_path).ok();
+                continue;
+            }
+
+            let result = info_file
+                .write_all(content.as_bytes())
+                .and_then(|_| std::fs::rename(&path, &target));
+
+            return match result {
+                Ok(_) => Ok(target),
+                Err(e) => {
+                    std::fs::remove_file(&info_path).ok();
+                    Err(e)
+                }
+            };
+        }
+
+        unreachable!()
+    }
+
+    /// Resolves the trash directories which can be used for a file, in the order of preference.
+    #[cfg(unix)]
+    fn resolve(&self, path: &Path) -> io::Result<Vec<TrashDir>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let device = std::fs::symlink_metadata(path)?.dev();
+
+        // The home trash is used for files on the same device
+        if let Some(home) = &self.home {
+            let home_device = home
+                .ancestors()
+                .find_map(|dir| std::fs::metadata(dir).ok())
+                .map(|meta| meta.dev());
+
+            if home_device == Option::Some(device) {
+                return Ok(vec![TrashDir {
+                    path: home.clone(),
+                    top_dir: Option::None,
+                }]);
+            }
+        }
+
+        // Otherwise the trash of the mount point
+        let mut top_dir = path;
+        while let Some(parent) = top_dir.parent() {
+            match std::fs::metadata(parent) {
+                Ok(meta) if meta.dev() == device => top_dir = parent,
+                _ => break,
+            }
+        }
+
+        let uid = unsafe { libc::getuid() };
+        Ok(mount_trash_dirs(top_dir, uid)
+            .into_iter()
+            .map(|path| TrashDir {
+                path,
+                top_dir: Option::Some(top_dir.to_path_buf()),
+            })
+            .collect())
+    }
+
+    /// Only the home trash is available on this platform.
+    #[cfg(not(unix))]
+    fn resolve(&self, _path: &Path) -> io::Result<Vec<TrashDir>> {
+        match &self.home {
+            Some(home) => Ok(vec![TrashDir {
+                path: home.clone(),
+                top_dir: Option::None,
+            }]),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "home trash not found",
+            )),
+        }
+    }
+}
+
+/// Returns the trash directories of a mount point in the order of preference.
+/// `$topdir/.Trash/$uid` can only be used if `$topdir/.Trash` is a sticky directory and not a
+/// symlink, `$topdir/.Trash-$uid` is the fallback if it can't be used.
+#[cfg(unix)]
+fn mount_trash_dirs(top_dir: &Path, uid: u32) -> Vec<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let shared = top_dir.join(".Trash");
+    let is_sticky = std::fs::symlink_metadata(&shared)
+        .map(|meta| meta.is_dir() && meta.permissions().mode() & 0o1000 != 0)
+        .unwrap_or(false);
+
+    let fallback = top_dir.join(format!(".Trash-{}", uid));
+    match is_sticky {
+        true => vec![shared.join(uid.to_string()), fallback],
+        false => vec![fallback],
+    }
+}
+
+/// Returns the name of a file in the trash, names are suffixed by a counter on collisions.
+fn trash_name(name: &std::ffi::OsStr, index: usize) -> std::ffi::OsString {
+    if index == 1 {
+        return name.to_os_string();
+    }
+
+    let path = Path::new(name);
+    let mut result = path.file_stem().unwrap_or(name).to_os_string();
+    result.push(format!(".{}", index));
+
+    if let Some(extension) = path.extension() {
+        result.push(".");
+        result.push(extension);
+    }
+
+    result
+}
+
+/// Percent-encodes a path as required in `.trashinfo` files.
+fn encode_path(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().as_bytes().to_vec();
+
+    let mut encoded = String::new();
+    for byte in &bytes {
+        if byte.is_ascii_alphanumeric() || UNRESERVED.contains(byte) {
+            encoded.push(*byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trash::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(trash_name("a.txt".as_ref(), 1), "a.txt");
+        assert_eq!(trash_name("a.txt".as_ref(), 3), "a.3.txt");
+        assert_eq!(trash_name("a".as_ref(), 2), "a.2");
+        assert_eq!(
+            encode_path(Path::new("/home/ä b%.txt")),
+            "/home/%C3%A4%20b%25.txt"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn mount_trash() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let top_dir = std::env::temp_dir().join(format!("cleanup-mount-{}", std::process::id()));
+        std::fs::create_dir_all(top_dir.join(".Trash")).unwrap();
+        assert_eq!(
+            mount_trash_dirs(&top_dir, 1000),
+            vec![top_dir.join(".Trash-1000")]
+        );
+
+        let sticky = std::fs::Permissions::from_mode(0o1777);
+        std::fs::set_permissions(top_dir.join(".Trash"), sticky).unwrap();
+        assert_eq!(
+            mount_trash_dirs(&top_dir, 1000),
+            vec![top_dir.join(".Trash/1000"), top_dir.join(".Trash-1000")]
+        );
+
+        std::fs::remove_dir_all(&top_dir).unwrap();
+    }
+
+    #[test]
+    fn collisions() {
+        let dir = std::env::temp_dir().join(format!("cleanup-trash-{}", std::process::id()));
+        let trash = Trash::at(dir.join("Trash"));
+        std::fs::create_dir_all(dir.join("Trash/files")).unwrap();
+        std::fs::write(dir.join("Trash/files/a.txt"), "").unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+
+        // Names taken by a file without info-file are skipped without leaving one behind
+        let target = trash.put(&dir.join("a.txt")).unwrap();
+        assert_eq!(target.file_name().unwrap(), "a.2.txt");
+        assert!(dir.join("Trash/info/a.2.txt.trashinfo").exists());
+        assert!(!dir.join("Trash/info/a.txt.trashinfo").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
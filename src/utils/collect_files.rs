@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+use crate::sorter::Event;
+
+/// Collects all files inside of a directory, recursively.
+/// Errors are reported via `callback`, returns how many occurred.
+pub fn collect_files<F: FnMut(Event)>(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    callback: &mut F,
+) -> usize {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            callback(Event::Failed(e));
+            return 1;
+        }
+    };
+
+    let mut errored = 0;
+    for entry in entries {
+        match entry {
+            Err(e) => {
+                callback(Event::Failed(e));
+                errored += 1;
+            }
+            Ok(entry) => {
+                let path = entry.path();
+
+                if path.is_dir() {
+                    errored += collect_files(&path, files, callback);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    errored
+}
@@ -1,5 +1,5 @@
+pub use collect_files::*;
 pub use resolve_directories::*;
-pub use wrap_string::*;
 
+mod collect_files;
 mod resolve_directories;
-mod wrap_string;
@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use path_absolutize::Absolutize;
+
+use crate::error::Error;
+use crate::route::Route;
+
+/**
+* Resolves source and target directories.
+*/
+pub fn resolve_directories(
+    sources: &[PathBuf],
+    target: &Path,
+    dry_run: bool,
+) -> Result<(Vec<PathBuf>, PathBuf), Error> {
+    let mut source_paths = Vec::new();
+    let target_path = target.absolutize().unwrap();
+
+    // Check if source-dirs exist
+    for source in sources {
+        let source_path = source.absolutize().unwrap();
+
+        if !source_path.exists() {
+            return Err(Error::SourceNotFound(source_path));
+        }
+
+        // The same directory could be passed twice
+        if !source_paths.contains(&source_path) {
+            source_paths.push(source_path);
+        }
+    }
+
+    // Create missing directories
+    if !dry_run {
+        if let Err(e) = std::fs::create_dir_all(&target_path) {
+            return Err(Error::TargetUnavailable(target_path, e));
+        }
+    }
+
+    Ok((source_paths, target_path))
+}
+
+/**
+* Resolves the directories of routes, they're created like the target.
+*/
+pub fn resolve_routes(routes: &[Route], dry_run: bool) -> Result<Vec<Route>, Error> {
+    let mut resolved = Vec::new();
+
+    for route in routes {
+        let directory = route.directory.absolutize().unwrap();
+
+        if !dry_run {
+            if let Err(e) = std::fs::create_dir_all(&directory) {
+                return Err(Error::TargetUnavailable(directory, e));
+            }
+        }
+
+        resolved.push(Route {
+            directory,
+            ..route.clone()
+        });
+    }
+
+    Ok(resolved)
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::route::Route;
+
+/// XDG user directories and the extensions of the files belonging into them.
+pub const USER_DIRS: &[(&str, &str, &[&str])] = &[
+    (
+        "PICTURES",
+        "Pictures",
+        &[
+            "jpg", "jpeg", "png", "gif", "webp", "heic", "avif", "bmp", "svg", "tif", "tiff",
+            "dng", "cr2", "cr3", "nef", "arw", "raf", "orf", "rw2",
+        ],
+    ),
+    (
+        "MUSIC",
+        "Music",
+        &[
+            "mp3", "flac", "ogg", "oga", "opus", "m4a", "aac", "wav", "wma", "aiff",
+        ],
+    ),
+    (
+        "VIDEOS",
+        "Videos",
+        &[
+            "mp4", "mkv", "avi", "mov", "webm", "m4v", "wmv", "flv", "mpg", "mpeg",
+        ],
+    ),
+    (
+        "DOCUMENTS",
+        "Documents",
+        &[
+            "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "xls", "xlsx", "ods", "csv", "ppt",
+            "pptx", "odp", "epub",
+        ],
+    ),
+];
+
+/**
+ * Parses the content of a `user-dirs.dirs` file, e.g. `XDG_PICTURES_DIR="$HOME/Bilder"`.
+ * Returns the directories by their name, e.g. `PICTURES`. Directories set to `$HOME` are disabled
+ * and left out.
+ */
+pub fn parse_user_dirs(content: &str, home: &Path) -> HashMap<String, PathBuf> {
+    let mut dirs = HashMap::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        let name = match key
+            .trim()
+            .strip_prefix("XDG_")
+            .and_then(|key| key.strip_suffix("_DIR"))
+        {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+        let path = match value.strip_prefix("$HOME") {
+            Some(relative) => home.join(relative.trim_start_matches('/')),
+            None if value.starts_with('/') => PathBuf::from(value),
+            None => continue,
+        };
+
+        if path != home {
+            dirs.insert(name.to_string(), path);
+        }
+    }
+
+    dirs
+}
+
+/**
+ * Resolves `$XDG_CONFIG_HOME`, or `~/.config` if it isn't set to an absolute path.
+ */
+pub fn config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| Option::Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))
+}
+
+/**
+ * Resolves the routes of pictures, music, videos and documents into the XDG user directories.
+ * The directories are read from `$XDG_CONFIG_HOME/user-dirs.dirs`, the defaults such as `~/Pictures`
+ * are used if it doesn't exist or lacks one of them.
+ */
+pub fn routes() -> Vec<Route> {
+    let home = match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home),
+        None => return Vec::new(),
+    };
+
+    let config_home = config_home().unwrap_or_else(|| home.join(".config"));
+
+    let content = std::fs::read_to_string(config_home.join("user-dirs.dirs")).unwrap_or_default();
+    let configured = parse_user_dirs(&content, &home);
+
+    USER_DIRS
+        .iter()
+        .filter_map(|(name, default, extensions)| {
+            let directory = match configured.get(*name) {
+                Some(directory) => directory.clone(),
+                None if content.contains(&format!("XDG_{}_DIR", name)) => return Option::None,
+                None => home.join(default),
+            };
+
+            Option::Some(Route::new(extensions, directory))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::xdg::*;
+
+    #[test]
+    fn user_dirs() {
+        let content = "# Comment\nXDG_PICTURES_DIR=\"$HOME/Bilder\"\nXDG_MUSIC_DIR=\"/mnt/Musik\"\nXDG_VIDEOS_DIR=\"$HOME/\"\nXDG_DESKTOP_DIR=\"$HOME/Schreibtisch\"";
+        let dirs = parse_user_dirs(content, Path::new("/home/a"));
+
+        assert_eq!(dirs.get("PICTURES"), Some(&PathBuf::from("/home/a/Bilder")));
+        assert_eq!(dirs.get("MUSIC"), Some(&PathBuf::from("/mnt/Musik")));
+        assert_eq!(
+            dirs.get("DESKTOP"),
+            Some(&PathBuf::from("/home/a/Schreibtisch"))
+        );
+        assert_eq!(dirs.get("VIDEOS"), None);
+    }
+}
@@ -1,10 +1,14 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
 use assert_cmd::prelude::*;
 
-mod lib;
+mod common;
 
 #[test]
 fn simple_sort() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".").assert().success();
 
         test(
@@ -25,7 +29,7 @@ fn simple_sort() {
 
 #[test]
 fn custom_target() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".").arg("sorted").assert().success();
 
         test(
@@ -45,7 +49,7 @@ fn custom_target() {
 
 #[test]
 fn exclude_extensions() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".")
             .arg("--exclude")
             .arg("txt,mp4")
@@ -69,7 +73,7 @@ fn exclude_extensions() {
 
 #[test]
 fn include_extensions() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".")
             .arg("--include")
             .arg("txt,psd")
@@ -91,9 +95,209 @@ fn include_extensions() {
     });
 }
 
+#[test]
+fn attached_values() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--include=txt")
+            .arg("-lmy.log")
+            .assert()
+            .success();
+
+        test(
+            vec![
+                ".archive/txt/t1.txt",
+                ".archive/txt/t2.txt",
+                ".archive/my.log",
+                "f1.psd",
+                "m1.mp4",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn repeated_flags() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("-i")
+            .arg("txt")
+            .arg("-i")
+            .arg("mp4")
+            .assert()
+            .success();
+
+        test(
+            vec![
+                ".archive/txt/t1.txt",
+                ".archive/mp4/m1.mp4",
+                "f1.psd",
+                "f2.psd",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn repeated_flags_with_commas() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::write(dir.join("a.b,c"), "").unwrap();
+
+        // Each value is split unless its commas are escaped, flags which can only be passed once
+        // take the last value
+        cmd.arg(".")
+            .arg("-i")
+            .arg("b\\,c,txt")
+            .arg("-i")
+            .arg("psd")
+            .arg("-l")
+            .arg("a.log")
+            .arg("-l")
+            .arg("b.log")
+            .assert()
+            .success();
+
+        test(
+            vec![
+                ".archive/b,c/a.b,c",
+                ".archive/txt/t1.txt",
+                ".archive/psd/f1.psd",
+                ".archive/b.log",
+                "m1.mp4",
+            ],
+            true,
+        );
+        test(vec![".archive/a.log"], false);
+    });
+
+    // Values of the environment and the config-file follow the same rule
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join(".config/cleanup")).unwrap();
+        std::fs::write(
+            dir.join(".config/cleanup/config"),
+            "exclude = txt,psd\nexclude = mp4\n",
+        )
+        .unwrap();
+
+        cmd.arg(".").assert().success();
+        test(vec!["t1.txt", "f1.psd", "m1.mp4"], true);
+    });
+
+    common::test_command(|cmd, test| {
+        cmd.env("CLEANUP_INCLUDE", "mp4,psd")
+            .arg(".")
+            .assert()
+            .success();
+        test(
+            vec![".archive/mp4/m1.mp4", ".archive/psd/f1.psd", "t1.txt"],
+            true,
+        );
+    });
+}
+
+#[test]
+fn typed_arguments() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--keep-newest=many")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains(
+                "Invalid value for --keep-newest: expected an integer but got many",
+            ));
+
+        test(vec!["t1.txt", "m1.mp4"], true);
+    });
+}
+
+#[test]
+fn ambiguous_flags() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("-dilog")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("Ambiguous flags -dilog"));
+
+        test(vec!["t1.txt", "m1.mp4"], true);
+    });
+}
+
+#[test]
+fn conflicting_flags() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--include=txt")
+            .arg("--exclude=mp4")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains(
+                "Flag --include can't be combined with --exclude.",
+            ));
+
+        test(vec!["t1.txt", "m1.mp4"], true);
+    });
+
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--restore")
+            .arg("--keep-newest=1")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains(
+                "Flag --restore can't be combined with --keep-newest.",
+            ));
+
+        test(vec!["t1.txt", "m1.mp4"], true);
+    });
+}
+
+#[test]
+fn conflicting_config() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join(".config/cleanup")).unwrap();
+        std::fs::write(dir.join(".config/cleanup/config"), "exclude = psd\n").unwrap();
+
+        // The command line takes precedence over conflicting flags of the config-file
+        cmd.arg(".").arg("--include=txt").assert().success();
+
+        test(vec![".archive/txt/t1.txt", "f1.psd", "m1.mp4"], true);
+    });
+}
+
+#[test]
+fn invalid_config() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join(".config/cleanup")).unwrap();
+        std::fs::write(dir.join(".config/cleanup/config"), "unknown = yes\n").unwrap();
+
+        // Errors of the config-file are only reported if something would be sorted
+        cmd.arg("--help").assert().success();
+        test(vec!["f1.psd", "t1.txt", "m1.mp4"], true);
+    });
+
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join(".config/cleanup")).unwrap();
+        std::fs::write(dir.join(".config/cleanup/config"), "unknown = yes\n").unwrap();
+
+        cmd.arg(".")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("unknown"));
+        test(vec!["f1.psd", "t1.txt", "m1.mp4"], true);
+    });
+}
+
 #[test]
 fn dry_run() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".")
             .arg("--include")
             .arg("txt,mp4")
@@ -118,7 +322,7 @@ fn dry_run() {
 
 #[test]
 fn version_help() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".")
             .arg("-v")
             .assert()
@@ -146,14 +350,75 @@ fn version_help() {
 
 #[test]
 fn invalid_source() {
-    lib::test_command(|cmd, _| {
-        cmd.arg("./bar/foo").assert().failure();
+    common::test_command(|cmd, _| {
+        cmd.arg("./bar/foo")
+            .assert()
+            .code(3)
+            .stderr(predicates::str::contains("Source not found"));
+    });
+}
+
+#[test]
+fn invalid_usage() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--foo")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("Unknown flag: --foo"));
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(cmd.get_current_dir().unwrap())
+            .arg(".")
+            .arg("--exlude")
+            .arg("txt")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains(
+                "Argument 2: Unknown flag: --exlude, did you mean --exclude?",
+            ));
+
+        // Nothing should change
+        test(vec!["t1.txt", "m1.mp4", "f1.psd"], true);
+    });
+}
+
+#[test]
+fn destination_exists() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join(".archive/txt")).unwrap();
+        std::fs::write(dir.join(".archive/txt/t1.txt"), "").unwrap();
+
+        cmd.arg(".")
+            .assert()
+            .code(4)
+            .stderr(predicates::str::contains("destination already exists"));
+
+        test(vec!["t1.txt", ".archive/txt/t2.txt"], true);
+    });
+}
+
+#[test]
+fn no_extension() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::write(dir.join("Makefile"), "").unwrap();
+
+        // Files without an extension are skipped and don't count as failure
+        cmd.arg(".")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Skipped:"));
+
+        test(vec!["Makefile", ".archive/txt/t1.txt"], true);
     });
 }
 
 #[test]
 fn log_file() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".").arg("-l").assert().success();
 
         test(vec![".archive/cleanup.log"], true);
@@ -162,7 +427,7 @@ fn log_file() {
 
 #[test]
 fn custom_log_file() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
         cmd.arg(".")
             .arg("--log-file")
             .arg("hello.txt")
@@ -175,13 +440,862 @@ fn custom_log_file() {
 
 #[test]
 fn disable_log_file() {
-    lib::test_command(|cmd, test| {
+    common::test_command(|cmd, test| {
+        cmd.arg(".").arg("--no-log").assert().success();
+
+        test(vec![".archive/cleanup.log"], false);
+    });
+
+    common::test_command(|cmd, test| {
+        cmd.arg(".").arg("--log=false").assert().success();
+
+        test(vec![".archive/cleanup.log"], false);
+    });
+
+    // A log-file may be called like a boolean
+    common::test_command(|cmd, test| {
         cmd.arg(".")
             .arg("--log-file")
             .arg("false")
             .assert()
             .success();
 
+        test(vec![".archive/false"], true);
+    });
+
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--no-log")
+            .arg("-l")
+            .arg("my.log")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("Flag --log-file requires --log."));
+
+        test(vec!["t1.txt"], true);
+    });
+}
+
+#[test]
+fn interactive() {
+    common::test_command(|cmd, test| {
+        common::with_stdin(cmd.arg(".").arg("--interactive"), "M\nM\nM\n")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("[M]ove all .txt"));
+
+        test(
+            vec![
+                ".archive/txt/t1.txt",
+                ".archive/txt/t2.txt",
+                ".archive/mp4/m1.mp4",
+                ".archive/psd/f2.psd",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn interactive_quit() {
+    common::test_command(|cmd, test| {
+        common::with_stdin(cmd.arg(".").arg("--interactive"), "q\n")
+            .assert()
+            .success();
+
+        test(
+            vec!["f1.psd", "f2.psd", "t1.txt", "t2.txt", "m1.mp4", "m2.mp4"],
+            true,
+        );
+    });
+}
+
+#[test]
+fn multiple_sources() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join("other")).unwrap();
+        std::fs::write(dir.join("other/o1.txt"), "").unwrap();
+
+        cmd.arg(".")
+            .arg("other")
+            .arg("--target")
+            .arg("sorted")
+            .assert()
+            .success();
+
+        test(
+            vec![
+                "sorted/txt/o1.txt",
+                "sorted/txt/t1.txt",
+                "sorted/psd/f1.psd",
+                "sorted/cleanup.log",
+            ],
+            true,
+        );
+
+        let log = std::fs::read_to_string(dir.join("sorted/cleanup.log")).unwrap();
+        assert!(log.contains("o1.txt (source: "));
+    });
+}
+
+#[test]
+fn multiple_sources_positional() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join("other")).unwrap();
+        std::fs::write(dir.join("other/o1.txt"), "").unwrap();
+
+        cmd.arg(".").arg("other").arg("sorted").assert().success();
+
+        test(vec!["sorted/txt/o1.txt", "sorted/mp4/m1.mp4"], true);
+    });
+}
+
+#[test]
+fn ignore_file() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::write(
+            dir.join(".cleanupignore"),
+            "# Keep these\n*.txt\n!t2.txt\nm?.mp4\n",
+        )
+        .unwrap();
+
+        cmd.arg(".")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(".cleanupignore:2: *.txt"));
+
+        test(
+            vec![
+                "t1.txt",
+                "m1.mp4",
+                "m2.mp4",
+                ".archive/txt/t2.txt",
+                ".archive/psd/f1.psd",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn busy_downloads() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::write(dir.join("video.mkv.crdownload"), "").unwrap();
+        std::fs::write(dir.join("video.mkv"), "").unwrap();
+        std::fs::write(dir.join("paper.PART"), "").unwrap();
+
+        cmd.arg(".")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Busy:"));
+
+        test(
+            vec![
+                "video.mkv.crdownload",
+                "video.mkv",
+                "paper.PART",
+                ".archive/txt/t1.txt",
+            ],
+            true,
+        );
+    });
+
+    // Unfinished downloads are moved like any other file without the check
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::write(dir.join("video.mkv.crdownload"), "").unwrap();
+        std::fs::write(dir.join("video.mkv"), "").unwrap();
+
+        cmd.arg(".").arg("--no-busy-check").assert().success();
+
+        test(
+            vec![
+                ".archive/crdownload/video.mkv.crdownload",
+                ".archive/mkv/video.mkv",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn busy_recently_modified() {
+    common::test_command(|cmd, test| {
+        cmd.arg(".")
+            .arg("--min-age")
+            .arg("1h")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("modified 0s ago"));
+
+        test(vec!["t1.txt", "m1.mp4", "f1.psd"], true);
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn busy_open_files() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        let _open = std::fs::File::open(dir.join("t1.txt").canonicalize().unwrap()).unwrap();
+
+        cmd.arg(".")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("opened by process"));
+
+        test(vec!["t1.txt", ".archive/txt/t2.txt"], true);
+    });
+}
+
+#[test]
+fn restore() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        cmd.arg(".").assert().success();
+
+        // Collisions are handled like when sorting
+        std::fs::write(dir.join("t1.txt"), "").unwrap();
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--restore")
+            .assert()
+            .code(4)
+            .stderr(predicates::str::contains("destination already exists"));
+
+        test(
+            vec![
+                "t1.txt",
+                "t2.txt",
+                "m1.mp4",
+                "m2.mp4",
+                "f1.psd",
+                "f2.psd",
+                ".archive/txt/t1.txt",
+                ".archive/cleanup.log",
+            ],
+            true,
+        );
+        test(
+            vec![".archive/mp4", ".archive/psd", ".archive/txt/t2.txt"],
+            false,
+        );
+    });
+}
+
+#[test]
+fn restore_without_log() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join("sorted/pdf/nested")).unwrap();
+        std::fs::write(dir.join("sorted/pdf/nested/p1.pdf"), "").unwrap();
+
+        cmd.arg(".")
+            .arg("sorted")
+            .arg("--flatten")
+            .arg("--no-log")
+            .assert()
+            .success();
+
+        test(vec!["p1.pdf", "sorted"], true);
+        test(vec!["sorted/pdf", "sorted/cleanup.log"], false);
+    });
+}
+
+#[test]
+fn rename() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::write(dir.join("My%20Photo.JPG"), "").unwrap();
+
+        cmd.arg(".")
+            .arg("--rename")
+            .arg("url-decode,spaces=-,lowercase")
+            .assert()
+            .success();
+
+        test(
+            vec![".archive/JPG/my-photo.jpg", ".archive/txt/t1.txt"],
+            true,
+        );
+
+        // The original name is restored from the log-file
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--restore")
+            .assert()
+            .success();
+
+        test(vec!["My%20Photo.JPG", "t1.txt"], true);
+        test(vec![".archive/JPG"], false);
+    });
+}
+
+#[test]
+#[cfg(unix)]
+fn rename_and_restore() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    common::test_command(|cmd, _| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        let names: Vec<&OsStr> = [
+            &b"My Photo.JPG"[..],
+            b"a%20b.TXT",
+            b"My -> File.TXT",
+            b"Caf\xe9 Menu.TXT",
+        ]
+        .iter()
+        .map(|name| OsStr::from_bytes(name))
+        .collect();
+
+        // Each file contains its own name to tell them apart after being renamed
+        for name in &names {
+            std::fs::write(dir.join(name), name.as_bytes()).unwrap();
+        }
+
+        cmd.arg(".")
+            .arg("--rename")
+            .arg("url-decode,spaces=-,lowercase")
+            .assert()
+            .success();
+
+        for name in &names {
+            assert!(!dir.join(name).exists());
+        }
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--restore")
+            .assert()
+            .success();
+
+        for name in &names {
+            assert_eq!(std::fs::read(dir.join(name)).unwrap(), name.as_bytes());
+        }
+    });
+}
+
+#[test]
+fn invalid_rename() {
+    common::test_command(|cmd, _| {
+        cmd.arg(".")
+            .arg("--rename")
+            .arg("uppercase")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("Unknown transform: uppercase"));
+    });
+}
+
+#[test]
+fn bundles() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        for file in [
+            "IMG_1.jpg",
+            "IMG_1.xmp",
+            "IMG_2.CR2",
+            "IMG_2.CR2.xmp",
+            "movie.srt",
+            "lonely.srt",
+            "scene.blend",
+            "scene.blend1",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        // Primary files which have already been moved are found as well
+        std::fs::create_dir_all(dir.join(".archive/mp4")).unwrap();
+        std::fs::write(dir.join(".archive/mp4/movie.mp4"), "").unwrap();
+
+        cmd.arg(".")
+            .arg("--bundle")
+            .arg("blend:blend1")
+            .assert()
+            .success();
+
+        test(
+            vec![
+                ".archive/jpg/IMG_1.xmp",
+                ".archive/CR2/IMG_2.CR2.xmp",
+                ".archive/mp4/movie.srt",
+                ".archive/srt/lonely.srt",
+                ".archive/blend/scene.blend1",
+            ],
+            true,
+        );
+        test(vec![".archive/xmp", ".archive/blend1"], false);
+    });
+}
+
+#[test]
+fn bundles_with_rename() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        for file in ["IMG_1.JPG", "IMG_1.xmp", "IMG_2.xmp"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        // Primary files are found under their new name, no matter which file is moved first
+        std::fs::create_dir_all(dir.join(".archive/JPG")).unwrap();
+        std::fs::write(dir.join(".archive/JPG/img_2.jpg"), "").unwrap();
+
+        cmd.arg(".")
+            .arg("--rename")
+            .arg("lowercase")
+            .assert()
+            .success();
+
+        test(
+            vec![
+                ".archive/JPG/img_1.jpg",
+                ".archive/JPG/img_1.xmp",
+                ".archive/JPG/img_2.xmp",
+            ],
+            true,
+        );
+        test(vec![".archive/xmp"], false);
+    });
+}
+
+#[test]
+#[cfg(unix)]
+fn non_utf8_names() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    common::test_command(|cmd, _| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+        let extension = OsStr::from_bytes(b"data.\xff");
+        std::fs::write(dir.join(name), "").unwrap();
+        std::fs::write(dir.join(extension), "").unwrap();
+
+        cmd.arg(".").assert().success();
+
+        assert!(dir.join(".archive/txt").join(name).exists());
+        assert!(dir
+            .join(".archive")
+            .join(OsStr::from_bytes(b"\xff"))
+            .join(extension)
+            .exists());
+
+        let log = std::fs::read_to_string(dir.join(".archive/cleanup.log")).unwrap();
+        assert!(log.contains("caf\\xE9.txt"));
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--restore")
+            .assert()
+            .success();
+
+        assert!(dir.join(name).exists());
+        assert!(dir.join(extension).exists());
+    });
+}
+
+#[test]
+fn xdg_user_dirs() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        let home = std::env::current_dir().unwrap().join(&dir).join(".home");
+        std::fs::create_dir_all(home.join(".config")).unwrap();
+        std::fs::write(
+            home.join(".config/user-dirs.dirs"),
+            "XDG_VIDEOS_DIR=\"$HOME/Filme\"\nXDG_DOCUMENTS_DIR=\"$HOME/\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("m1.srt"), "").unwrap();
+
+        cmd.env("HOME", &home)
+            .env_remove("XDG_CONFIG_HOME")
+            .arg(".")
+            .arg("--xdg")
+            .assert()
+            .success();
+
+        // Disabled directories and unknown types use the target
+        test(
+            vec![
+                ".home/Filme/m1.mp4",
+                ".home/Filme/m2.mp4",
+                ".home/Filme/m1.srt",
+                ".archive/txt/t1.txt",
+                ".archive/psd/f1.psd",
+            ],
+            true,
+        );
+        test(vec![".archive/mp4", ".home/Documents"], false);
+    });
+}
+
+#[test]
+fn routes() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        let root = dir.canonicalize().unwrap();
+        let routes = format!(
+            "mp4,psd={};txt={}",
+            root.join("media").display(),
+            root.join("docs/inbox").display()
+        );
+
+        // Relative directories are rejected
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--route")
+            .arg("mp4,psd=media")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains(
+                "Route directory must be absolute",
+            ));
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--route")
+            .arg(&routes)
+            .arg("--dry-run")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(" | Route: mp4, psd -> "))
+            .stdout(predicates::str::contains(format!(
+                "m1.mp4 -> {}",
+                root.join("media/m1.mp4").display()
+            )))
+            .stdout(predicates::str::contains(format!(
+                "t1.txt -> {}",
+                root.join("docs/inbox/t1.txt").display()
+            )));
+
+        test(vec!["media", "docs"], false);
+
+        cmd.arg(".").arg("--route").arg(&routes).assert().success();
+
+        test(
+            vec!["media/m1.mp4", "media/f2.psd", "docs/inbox/t1.txt"],
+            true,
+        );
+        test(vec![".archive/mp4", ".archive/txt"], false);
+
+        // Routes are validated like the target
+        std::fs::write(dir.join("blocker"), "").unwrap();
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--route")
+            .arg(format!("pdf={}", root.join("blocker/pdf").display()))
+            .assert()
+            .code(1)
+            .stderr(predicates::str::contains("Failed to create target"));
+    });
+}
+
+#[test]
+#[cfg(unix)]
+fn routes_across_devices() {
+    use std::os::unix::fs::MetadataExt;
+
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        let device = std::fs::metadata(&dir).unwrap().dev();
+
+        // Needs a directory on another file-system, which isn't available everywhere
+        let other = match ["/dev/shm", "/run/user"]
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| std::fs::metadata(path).is_ok_and(|meta| meta.dev() != device))
+        {
+            Some(other) => other.join(format!("cleanup-test-{}", std::process::id())),
+            None => return,
+        };
+
+        std::fs::write(dir.join("m1.mp4"), "video").unwrap();
+        cmd.arg(".")
+            .arg("--route")
+            .arg(format!("mp4={}", other.display()))
+            .assert()
+            .success();
+
+        let content = std::fs::read_to_string(other.join("m1.mp4"));
+        std::fs::remove_dir_all(&other).unwrap();
+
+        assert_eq!(content.unwrap(), "video");
+        test(vec![".archive/txt/t1.txt"], true);
+        test(vec!["m1.mp4", "m2.mp4"], false);
+    });
+}
+
+#[test]
+fn config_file() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join(".config/cleanup")).unwrap();
+        std::fs::write(
+            dir.join(".config/cleanup/config"),
+            "exclude = psd\nlog = no\n",
+        )
+        .unwrap();
+
+        // Environment variables take precedence over the config-file
+        cmd.env("CLEANUP_EXCLUDE", "mp4");
+        cmd.arg(".")
+            .arg("--print-config")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("exclude = mp4"))
+            .stdout(predicates::str::contains("# env CLEANUP_EXCLUDE"))
+            .stdout(predicates::str::contains("log = false"));
+
+        test(vec!["t1.txt", "m1.mp4"], true);
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .env(
+                "XDG_CONFIG_HOME",
+                dir.canonicalize().unwrap().join(".config"),
+            )
+            .arg(".")
+            .assert()
+            .success();
+
+        test(
+            vec![".archive/txt/t1.txt", ".archive/mp4/m1.mp4", "f1.psd"],
+            true,
+        );
         test(vec![".archive/cleanup.log"], false);
     });
 }
+
+#[test]
+fn completions() {
+    for (shell, expected) in [
+        ("bash", "complete -F _cleanup cleanup"),
+        ("zsh", "#compdef cleanup"),
+        ("fish", "complete -c cleanup -s t -l target"),
+    ] {
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .arg("completions")
+            .arg(shell)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(expected));
+    }
+
+    Command::cargo_bin("cleanup")
+        .unwrap()
+        .arg("completions")
+        .arg("powershell")
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains(
+            "Invalid value for <shell>: expected one of bash, zsh, fish but got powershell",
+        ));
+
+    Command::cargo_bin("cleanup")
+        .unwrap()
+        .arg("completions")
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("Missing value labeled \"shell\""));
+
+    Command::cargo_bin("cleanup")
+        .unwrap()
+        .arg("completions")
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Usage: cleanup completions <shell: ",
+        ));
+}
+
+#[test]
+fn man_page() {
+    Command::cargo_bin("cleanup")
+        .unwrap()
+        .env("HOME", "/home/packager")
+        .env_remove("XDG_CONFIG_HOME")
+        .arg("man")
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with(".TH CLEANUP 1"))
+        .stdout(predicates::str::contains("\\fB\\-\\-restore\\fR"))
+        .stdout(predicates::str::contains(".SH EXIT STATUS\n.TP\n.B 0\n"))
+        .stdout(predicates::str::contains(
+            ".SH FILES\n.TP\n.I $XDG_CONFIG_HOME/cleanup/config (default ~/.config/cleanup/config)\n",
+        ))
+        .stdout(predicates::prelude::PredicateBooleanExt::not(
+            predicates::str::contains("packager"),
+        ));
+}
+
+#[test]
+fn directory_called_like_a_command() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        std::fs::create_dir_all(dir.join("man")).unwrap();
+        std::fs::write(dir.join("man/a.txt"), "").unwrap();
+
+        cmd.arg("--").arg("man").assert().success();
+
+        test(vec!["man/.archive/txt/a.txt", "t1.txt"], true);
+    });
+}
+
+#[test]
+fn expire_after() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        cmd.arg(".").assert().success();
+
+        // Pretend t1.txt has been archived long ago
+        let archive = dir.join(".archive").canonicalize().unwrap();
+        let mut log = std::fs::OpenOptions::new()
+            .append(true)
+            .open(archive.join("cleanup.log"))
+            .unwrap();
+
+        writeln!(
+            log,
+            "2000-01-01 00:00:00 [MOVED] {} -> {}",
+            dir.canonicalize().unwrap().join("t1.txt").display(),
+            archive.join("txt/t1.txt").display()
+        )
+        .unwrap();
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--expire-after")
+            .arg("30d")
+            .arg("--dry-run")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Expired:"));
+
+        test(vec![".archive/txt/t1.txt"], true);
+
+        // Expired files are moved to the trash
+        let data_home = std::env::current_dir().unwrap().join(&dir).join(".data");
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .env("XDG_DATA_HOME", &data_home)
+            .arg(".")
+            .arg("--expire-after")
+            .arg("30d")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Trashed:"));
+
+        test(
+            vec![
+                ".archive/txt/t2.txt",
+                ".archive/mp4/m1.mp4",
+                ".data/Trash/files/t1.txt",
+                ".data/Trash/info/t1.txt.trashinfo",
+            ],
+            true,
+        );
+        test(vec![".archive/txt/t1.txt"], false);
+
+        let info = std::fs::read_to_string(data_home.join("Trash/info/t1.txt.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=/"));
+        assert!(info.contains("/.archive/txt/t1.txt\nDeletionDate="));
+    });
+}
+
+#[test]
+fn expire_after_invalid() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        cmd.arg(".").assert().success();
+
+        // Plain numbers are ambiguous and too long durations don't overflow
+        for age in ["30", "99999999999999999999d", "9999999999999999w"] {
+            Command::cargo_bin("cleanup")
+                .unwrap()
+                .current_dir(&dir)
+                .arg(".")
+                .arg("--expire-after")
+                .arg(age)
+                .assert()
+                .code(2);
+        }
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--min-age")
+            .arg("90")
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("a duration with a unit"));
+
+        test(vec![".archive/txt/t1.txt", ".archive/mp4/m1.mp4"], true);
+    });
+}
+
+#[test]
+fn keep_newest_and_max_size() {
+    common::test_command(|cmd, test| {
+        let dir = cmd.get_current_dir().unwrap().to_path_buf();
+        cmd.arg(".").assert().success();
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--keep-newest")
+            .arg("1")
+            .arg("--permanent")
+            .assert()
+            .success();
+
+        for category in ["txt", "mp4", "psd"] {
+            let files = std::fs::read_dir(dir.join(".archive").join(category)).unwrap();
+            assert_eq!(files.count(), 1);
+        }
+
+        // Only non-empty files take up space
+        std::fs::create_dir_all(dir.join(".archive/pdf")).unwrap();
+        std::fs::write(dir.join(".archive/pdf/large.pdf"), "0123456789").unwrap();
+
+        Command::cargo_bin("cleanup")
+            .unwrap()
+            .current_dir(&dir)
+            .arg(".")
+            .arg("--max-size")
+            .arg("5")
+            .arg("--permanent")
+            .assert()
+            .success();
+
+        test(vec![".archive/cleanup.log", ".archive/pdf"], true);
+        test(vec![".archive/pdf/large.pdf"], false);
+    });
+}
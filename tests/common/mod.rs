@@ -0,0 +1,107 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use assert_cmd::prelude::*;
+use rand::Rng;
+use remove_dir_all::*;
+
+/// Verifies a file-tree based on a list of entries
+pub fn verify_file_tree(paths: Vec<String>, expected: bool) {
+    for path_str in paths {
+        let path = PathBuf::from(path_str);
+
+        if path.exists() != expected {
+            panic!("Path not found: {:?}", path);
+        };
+    }
+}
+
+/// Verifies the file-tree of a test relative to its directory.
+pub type TreeVerifier<'a> = &'a dyn Fn(Vec<&str>, bool);
+
+/// Removes the directory of a test once dropped, even if the test panicked.
+struct Cleanup<'a>(&'a str);
+
+impl Drop for Cleanup<'_> {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(self.0);
+    }
+}
+
+/// Creates a directory with test files, calls `test` with its path and removes it afterwards.
+pub fn test_dir<F: FnOnce(&str, TreeVerifier)>(test: F) {
+    let hash: String = (0..10)
+        .map(|_| rand::thread_rng().gen_range(97_u8, 122_u8) as char)
+        .collect();
+
+    let dir = format!("tests/{}/", hash);
+    std::fs::create_dir_all(&dir).unwrap();
+    let _cleanup = Cleanup(&dir);
+
+    // Create test files
+    for file in [
+        "t1.txt",
+        "t2.txt",
+        "m1.mp4",
+        "m2.mp4",
+        "f1.psd",
+        "f2.psd",
+        ".ignored-file",
+    ] {
+        let mut file_path = String::default();
+        file_path.push_str(&dir);
+        file_path.push_str(file);
+        std::fs::write(&file_path, "").unwrap();
+    }
+
+    // Execute tests
+    test(&dir, &|mut vec, expected| {
+        verify_file_tree(
+            vec.iter_mut()
+                .map(|s| {
+                    let mut string = String::default();
+                    string.push_str(&dir);
+                    string.push_str(s);
+                    string
+                })
+                .collect(),
+            expected,
+        )
+    });
+}
+
+/// Tests a command and compares the file-structure with the result.
+pub fn test_command(test: fn(&mut Command, TreeVerifier)) {
+    test_dir(|dir, verify| {
+        // Never pick up the config-file of the user running the tests
+        let config_home = std::fs::canonicalize(dir).unwrap().join(".config");
+
+        test(
+            Command::cargo_bin("cleanup")
+                .unwrap()
+                .current_dir(dir)
+                .env("XDG_CONFIG_HOME", config_home),
+            verify,
+        )
+    });
+}
+
+/// Runs a command with `input` passed to its stdin.
+pub fn with_stdin(cmd: &mut Command, input: &str) -> Output {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
@@ -0,0 +1,181 @@
+use cleanup_files::{Decision, Error, Event, FileErrorKind, FileResult, Options, Sorter};
+
+#[allow(dead_code)]
+mod common;
+
+#[test]
+fn sort() {
+    common::test_dir(|dir, test| {
+        let mut moved = 0;
+        let summary = Sorter::new(dir)
+            .run(|event| {
+                if let Event::Processed {
+                    result: FileResult::Moved(_),
+                    ..
+                } = event
+                {
+                    moved += 1;
+                }
+            })
+            .unwrap();
+
+        assert_eq!(moved, 6);
+        assert_eq!(summary.processed, 7);
+        assert!(summary.status().is_ok());
+
+        test(
+            vec![
+                ".archive/txt/t1.txt",
+                ".archive/mp4/m2.mp4",
+                ".archive/psd/f1.psd",
+                ".ignored-file",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn hidden_and_extensionless_files() {
+    common::test_dir(|dir, test| {
+        std::fs::write(format!("{}/.hidden.txt", dir), "").unwrap();
+        std::fs::write(format!("{}/Makefile", dir), "").unwrap();
+
+        // Files without an extension are skipped, hidden ones are moved like any other file
+        let mut skipped = Vec::new();
+        let summary = Sorter::new(dir)
+            .run(|event| {
+                if let Event::Processed {
+                    path,
+                    result: FileResult::Skipped,
+                    ..
+                } = event
+                {
+                    skipped.push(path.file_name().unwrap().to_owned());
+                }
+            })
+            .unwrap();
+
+        skipped.sort();
+        assert_eq!(skipped, [".ignored-file", "Makefile"]);
+        assert_eq!(summary.processed, 9);
+        assert!(summary.status().is_ok());
+
+        test(
+            vec![".archive/txt/.hidden.txt", ".ignored-file", "Makefile"],
+            true,
+        );
+    });
+}
+
+#[test]
+fn dry_run() {
+    common::test_dir(|dir, test| {
+        let summary = Sorter::new(dir)
+            .target(format!("{}/sorted", dir))
+            .options(Options {
+                dry_run: true,
+                included: Some(vec![String::from("txt")]),
+                ..Options::default()
+            })
+            .run(|event| {
+                if let Event::Processed { path, result, .. } = event {
+                    match path.extension().and_then(|e| e.to_str()) {
+                        Some("txt") => assert!(matches!(
+                            result,
+                            FileResult::Planned(dest) if dest.parent().unwrap().ends_with("sorted/txt")
+                        )),
+                        _ => assert!(matches!(result, FileResult::Skipped)),
+                    }
+                }
+            })
+            .unwrap();
+
+        assert_eq!(summary.errored, 0);
+        test(vec!["sorted", ".archive"], false);
+    });
+}
+
+#[test]
+fn missing_source() {
+    let result = Sorter::new("tests/does-not-exist").run(|_| ());
+    assert!(matches!(result, Err(Error::SourceNotFound(_))));
+}
+
+#[test]
+fn error_kinds() {
+    common::test_dir(|dir, _| {
+        std::fs::create_dir_all(format!("{}/.archive/txt", dir)).unwrap();
+        std::fs::write(format!("{}/.archive/txt/t1.txt", dir), "").unwrap();
+        std::fs::write(format!("{}/Makefile", dir), "").unwrap();
+
+        let mut kinds = Vec::new();
+        let summary = Sorter::new(dir)
+            .run(|event| {
+                if let Event::Processed {
+                    result: FileResult::Errored(e),
+                    ..
+                } = event
+                {
+                    kinds.push(e.kind);
+                }
+            })
+            .unwrap();
+
+        assert_eq!(kinds, vec![FileErrorKind::DestinationExists]);
+        assert_eq!(summary.errored, 1);
+    });
+}
+
+#[test]
+fn decide() {
+    common::test_dir(|dir, test| {
+        let summary = Sorter::new(dir)
+            .run_with(
+                |path| match path.extension().unwrap().to_str().unwrap() {
+                    "txt" => Decision::Skip,
+                    "mp4" => Decision::MoveTo(format!("{}/videos", dir).into()),
+                    _ => Decision::Move,
+                },
+                |_| (),
+            )
+            .unwrap();
+
+        assert_eq!(summary.processed, 7);
+        test(
+            vec![
+                "t1.txt",
+                "t2.txt",
+                "videos/mp4/m1.mp4",
+                "videos/mp4/m2.mp4",
+                ".archive/psd/f1.psd",
+            ],
+            true,
+        );
+    });
+}
+
+#[test]
+fn multiple_sources() {
+    common::test_dir(|dir, test| {
+        std::fs::create_dir_all(format!("{}/other", dir)).unwrap();
+        std::fs::write(format!("{}/other/o1.txt", dir), "").unwrap();
+
+        let mut from_other = 0;
+        let summary = Sorter::new(dir)
+            .source(format!("{}/other", dir))
+            .target(format!("{}/sorted", dir))
+            .run(|event| {
+                if let Event::Processed { source, .. } = event {
+                    if source.ends_with("other") {
+                        from_other += 1;
+                    }
+                }
+            })
+            .unwrap();
+
+        assert_eq!(summary.processed, 8);
+        assert_eq!(from_other, 1);
+        test(vec!["sorted/txt/o1.txt", "sorted/txt/t1.txt"], true);
+    });
+}